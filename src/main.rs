@@ -1,4 +1,4 @@
-use directories::ProjectDirs;
+use directories::{BaseDirs, ProjectDirs};
 use eframe::egui;
 use egui::{
     vec2, Color32, Context, Frame, Key, KeyboardShortcut, Layout, Modifiers, RichText, TextFormat,
@@ -7,32 +7,275 @@ use egui::{
 use fuzzy_matcher::skim::SkimMatcherV2;
 use fuzzy_matcher::FuzzyMatcher;
 use serde::{Deserialize, Serialize};
-use std::collections::HashSet;
+use std::collections::{hash_map::Entry, HashMap, HashSet, VecDeque};
 use std::fs;
-use std::path::PathBuf;
-use toml;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::time::Instant;
 
-const MAX_UNDO_HISTORY: usize = 20;
+const DEFAULT_UNDO_HISTORY_SIZE: usize = 20;
+const MAX_MESSAGE_HISTORY: usize = 100;
+const SINGLE_FILE_NAME: &str = "keybinds.json";
+const RECENT_FILE_NAME: &str = "recent.json";
+#[cfg(feature = "share")]
+const DEFAULT_SHARE_ENDPOINT: &str = "https://paste.rs";
+const DATA_DIR_ENV: &str = "CTRLSET_DATA_DIR";
+/// Set from `--data-dir`, which takes precedence over `CTRLSET_DATA_DIR`.
+static DATA_DIR_OVERRIDE: OnceLock<PathBuf> = OnceLock::new();
+/// Digits that pick one of the first nine entries in the app filter popup
+/// while its search is empty.
+const QUICK_PICK_KEYS: [(&str, Key); 9] = [
+    ("1", Key::Num1),
+    ("2", Key::Num2),
+    ("3", Key::Num3),
+    ("4", Key::Num4),
+    ("5", Key::Num5),
+    ("6", Key::Num6),
+    ("7", Key::Num7),
+    ("8", Key::Num8),
+    ("9", Key::Num9),
+];
+
+const TABLE_ROW_SPACING: f32 = 4.0;
+const COMPACT_ROW_SPACING: f32 = 0.0;
+const TABLE_FONT_SIZE: f32 = 14.0;
+const COMPACT_FONT_SIZE: f32 = 11.0;
+/// Id of the text edit a cell is changed in, so Insert mode can move its
+/// cursor.
+const CELL_EDIT_ID: &str = "cell_edit";
+/// Characters the jump labels are made of, home row first.
+const JUMP_LABEL_CHARS: &str = "asdfghjklqwertyuiopzxcvbnm";
+/// Id of the help popup's search box, to tell whether it's being typed in.
+const HELP_SEARCH_ID: &str = "help_search";
+const DESCRIPTION_WRAP_WIDTH: f32 = 400.0;
+const ZOOM_STEP: f32 = 0.1;
+const MIN_ZOOM: f32 = 0.5;
+const MAX_ZOOM: f32 = 3.0;
+
+/// Every command-mode command and its description. The help popup and the
+/// command palette are both generated from this table.
+const COMMANDS: &[(&str, &str)] = &[
+    ("w", "Save current application's keybinds"),
+    ("wq", "Save and quit"),
+    ("wa", "Save all applications"),
+    ("wqa", "Save all applications and quit"),
+    ("q", "Quit (asks first if anything is unsaved)"),
+    ("q!", "Force quit without saving"),
+    ("new <name>", "Create a new application group"),
+    (
+        "saveas <name>",
+        "Save a copy of this app under a new name and switch to it",
+    ),
+    ("consolidate", "Merge per-app files into keybinds.json"),
+    (
+        "revert",
+        "Discard unsaved changes to this app (:revert! confirms)",
+    ),
+    ("reload", "Reload config.toml"),
+    (
+        "set <option> [value]",
+        "Show or change a config option (set! also saves it)",
+    ),
+    ("datadir", "Open the keybinds data directory"),
+    ("configdir", "Open the config directory"),
+    ("config", "Open config.toml in your editor"),
+    ("source <path>", "Merge a keybinds JSON file from any path"),
+    ("<n>", "Jump to row n"),
+    (
+        "move <name>",
+        "Move selected keybind to another application",
+    ),
+    (
+        "keys <text>",
+        "Set the selected row's keys as typed, e.g. Numpad5",
+    ),
+    ("color #rrggbb", "Set the color of this app's name"),
+    (
+        "note <text>",
+        "Set the current application's note (empty clears it)",
+    ),
+    (
+        "only <modifier>",
+        "Show only ctrl, alt, shift or none keybinds (all resets)",
+    ),
+    (
+        "clean",
+        "Trim whitespace and remove duplicate keybinds in this app",
+    ),
+    (
+        "header <text>",
+        "Insert a section header above the selected row",
+    ),
+    (
+        "merge <source> <dest>",
+        "Move every keybind of one app into another",
+    ),
+    ("grep <pattern>", "List matching keybinds across all apps"),
+    ("export", "Open the export menu"),
+    ("import", "Open the import menu"),
+    ("add", "Add a keybind at the end of this app"),
+    (
+        "searchscope keys|desc|both",
+        "Limit searches to keys or descriptions",
+    ),
+    ("nohl", "Clear search highlighting, keeping the filter"),
+    ("sort uses", "Sort this app's keybinds by use count"),
+    (
+        "relevance",
+        "Toggle sorting search results by match quality",
+    ),
+    ("compact", "Toggle tighter rows and a smaller font"),
+    ("hide-empty", "Hide rows with no keys and no description"),
+    ("show-empty", "Show rows with no keys and no description"),
+    ("stats", "Count keybinds and missing descriptions"),
+    ("lint", "Jump to the next keybind without a description"),
+    ("toggle", "Disable or enable the selected keybind"),
+    (
+        "starred",
+        "List starred keybinds from all apps (again to go back)",
+    ),
+    ("yank", "Copy selected keybind to clipboard"),
+    ("yank-json", "Copy current application as JSON"),
+    ("paste-json", "Import keybinds from the clipboard"),
+    ("lock", "Disable editing (read-only mode)"),
+    ("unlock", "Re-enable editing"),
+    ("only-app", "Stay on this app (disables app switching)"),
+    ("all-apps", "Re-enable app switching"),
+    ("messages", "Show recent status messages"),
+    ("diff", "Show unsaved changes to this app"),
+    ("failed", "List data files that failed to load"),
+    ("history", "Browse and restore undo history"),
+    ("help", "Show this help menu"),
+];
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, Hash)]
 struct Keybind {
     keys: String,
     description: String,
     application: String,
+    #[serde(default)]
+    uses: u64,
+    #[serde(default)]
+    kind: RowKind,
+    #[serde(default = "default_true")]
+    enabled: bool,
+    #[serde(default)]
+    starred: bool,
+    /// Identifies the row independently of its position. Kept through saves
+    /// and loads; 0 until one is assigned.
+    #[serde(default)]
+    id: u64,
+}
+
+/// Whether a row is a keybind or a section header, whose text is kept in
+/// `description`.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash, Default)]
+#[serde(rename_all = "lowercase")]
+enum RowKind {
+    #[default]
+    Keybind,
+    Header,
+}
+
+impl RowKind {
+    fn is_keybind(&self) -> bool {
+        *self == RowKind::Keybind
+    }
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
 struct KeybindEntry {
     keys: String,
     description: String,
+    /// How often the keybind was marked as used. Omitted while zero.
+    #[serde(default, skip_serializing_if = "is_zero")]
+    uses: u64,
+    #[serde(default, skip_serializing_if = "RowKind::is_keybind")]
+    kind: RowKind,
+    /// Cleared for bindings that are commented out in the real config.
+    /// Omitted while enabled.
+    #[serde(default = "default_true", skip_serializing_if = "is_true")]
+    enabled: bool,
+    /// Listed by `:starred` across applications. Omitted unless set.
+    #[serde(default, skip_serializing_if = "is_false")]
+    starred: bool,
+    /// The row's `Keybind::id`. Files written before ids existed have none;
+    /// the rows get one on load.
+    #[serde(default, skip_serializing_if = "is_zero")]
+    id: u64,
+}
+
+/// Ids are left out, so an entry still equals its saved copy from before ids
+/// were assigned.
+impl PartialEq for KeybindEntry {
+    fn eq(&self, other: &Self) -> bool {
+        self.keys == other.keys
+            && self.description == other.description
+            && self.uses == other.uses
+            && self.kind == other.kind
+            && self.enabled == other.enabled
+            && self.starred == other.starred
+    }
+}
+
+impl Eq for KeybindEntry {}
+
+fn is_zero(n: &u64) -> bool {
+    *n == 0
+}
+
+fn is_true(b: &bool) -> bool {
+    *b
+}
+
+fn is_false(b: &bool) -> bool {
+    !*b
+}
+
+fn default_true() -> bool {
+    true
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 struct AppKeybinds {
     application: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    notes: String,
+    /// Accent color for the application's name, set with `:color`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    color: Option<[u8; 3]>,
     keybinds: Vec<KeybindEntry>,
 }
 
+/// A snapshot of the keybinds, applications and their notes and colors taken
+/// before a change, for undo and `:history`.
+#[derive(Clone)]
+struct UndoEntry {
+    keybinds: Vec<Keybind>,
+    applications: HashSet<String>,
+    app_notes: HashMap<String, String>,
+    app_colors: HashMap<String, [u8; 3]>,
+    current_application: String,
+    description: String,
+    timestamp: Instant,
+}
+
+/// One line of `:diff`, comparing the saved keybinds with the current ones.
+enum DiffLine {
+    Added(KeybindEntry),
+    Removed(KeybindEntry),
+    Modified {
+        old: KeybindEntry,
+        new: KeybindEntry,
+    },
+}
+
+/// Data files that couldn't be read or parsed, with the error for each.
+type LoadFailures = Vec<(PathBuf, String)>;
+
+/// The per-application files that loaded, and those that failed.
+type PerAppFiles = (Vec<(PathBuf, AppKeybinds)>, LoadFailures);
+
 struct FilteredItem {
     original_index: usize,
     match_indices: Option<Vec<usize>>,
@@ -48,9 +291,20 @@ enum Mode {
     Export,
     Import,
     Help,
+    History,
+    CommandPalette,
+    ImportMatch,
+    Messages,
+    Diff,
+    LoadFailures,
+    Grep,
+    QuitConfirm,
+    ForceQuitConfirm,
+    Jump,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default)]
 struct Keymap {
     up: String,
     down: String,
@@ -59,6 +313,8 @@ struct Keymap {
     goto_top: String,
     goto_bottom: String,
     insert_mode: String,
+    insert_sequence: String,
+    insert_text: String,
     normal_mode: String,
     normal_mode_alt: String,
     search_mode: String,
@@ -66,11 +322,21 @@ struct Keymap {
     undo: String,
     delete_line: String,
     delete_leader: String,
+    yank_leader: String,
+    quit_leader: String,
+    record_macro: String,
     new_line_below: String,
     new_line_above: String,
+    append_line: String,
+    move_down: String,
+    move_up: String,
+    mark_used: String,
     app_filter: String,
     export_menu: String,
     import_menu: String,
+    command_palette: String,
+    toggle_enabled: String,
+    jump: String,
     leader: String,
 }
 
@@ -84,6 +350,8 @@ impl Default for Keymap {
             goto_top: "G".into(),
             goto_bottom: "G".into(), // Special case for Shift+G
             insert_mode: "I".into(),
+            insert_sequence: "I".into(), // Special case for Shift+I
+            insert_text: "A".into(),
             normal_mode: "Escape".into(),
             normal_mode_alt: "Control+OpenBracket".into(),
             search_mode: "Slash".into(),
@@ -91,16 +359,542 @@ impl Default for Keymap {
             undo: "U".into(),
             delete_line: "D".into(), // For 'dd'
             delete_leader: "D".into(),
+            yank_leader: "Y".into(), // For 'yy'
+            quit_leader: "Z".into(), // Special case for Shift+Z, for 'ZZ'/'ZQ'
+            record_macro: "Q".into(),
             new_line_below: "O".into(),
             new_line_above: "O".into(), // Special case for Shift+O
+            append_line: "A".into(),    // Special case for Shift+A
+            move_down: "J".into(),      // Special case for Shift+J
+            move_up: "K".into(),        // Special case for Shift+K
+            mark_used: "M".into(),
             app_filter: "F".into(),
             export_menu: "E".into(),
             import_menu: "I".into(),
+            command_palette: "P".into(),
+            toggle_enabled: "T".into(),
+            jump: "S".into(),
             leader: "Space".into(),
         }
     }
 }
 
+impl Keymap {
+    /// Returns the `(field, value)` pairs whose key name `string_to_key` can't
+    /// map. `normal_mode_alt` is a chord and isn't checked.
+    fn unknown_keys(&self) -> Vec<(&'static str, &str)> {
+        let single = [
+            ("up", &self.up),
+            ("down", &self.down),
+            ("goto_top", &self.goto_top),
+            ("goto_bottom", &self.goto_bottom),
+            ("insert_mode", &self.insert_mode),
+            ("insert_sequence", &self.insert_sequence),
+            ("insert_text", &self.insert_text),
+            ("normal_mode", &self.normal_mode),
+            ("search_mode", &self.search_mode),
+            ("command_mode", &self.command_mode),
+            ("undo", &self.undo),
+            ("delete_line", &self.delete_line),
+            ("delete_leader", &self.delete_leader),
+            ("yank_leader", &self.yank_leader),
+            ("quit_leader", &self.quit_leader),
+            ("record_macro", &self.record_macro),
+            ("new_line_below", &self.new_line_below),
+            ("new_line_above", &self.new_line_above),
+            ("append_line", &self.append_line),
+            ("move_down", &self.move_down),
+            ("move_up", &self.move_up),
+            ("mark_used", &self.mark_used),
+            ("app_filter", &self.app_filter),
+            ("export_menu", &self.export_menu),
+            ("import_menu", &self.import_menu),
+            ("command_palette", &self.command_palette),
+            ("toggle_enabled", &self.toggle_enabled),
+            ("jump", &self.jump),
+            ("leader", &self.leader),
+        ];
+        let lists = [("left", &self.left), ("right", &self.right)];
+
+        single
+            .into_iter()
+            .map(|(field, value)| (field, value.as_str()))
+            .chain(
+                lists
+                    .into_iter()
+                    .flat_map(|(field, values)| values.iter().map(move |v| (field, v.as_str()))),
+            )
+            .filter(|(_, value)| string_to_key(value).is_none())
+            .collect()
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "kebab-case")]
+enum StorageMode {
+    /// One `<application>.json` file per application.
+    #[default]
+    PerApp,
+    /// A single `keybinds.json` holding every application.
+    Single,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+enum KeybindNotation {
+    /// `Ctrl+Shift+X`, as named by egui.
+    #[default]
+    Egui,
+    /// `C-x`, `M-S`, `RET`.
+    Emacs,
+    /// `<C-x>`, `<M-S>`, `<CR>`.
+    Vim,
+}
+
+/// How keys are drawn: as text in the configured notation, or as
+/// Mac-keyboard-style symbols such as `⌃⇧↑`.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+enum KeyDisplay {
+    #[default]
+    Raw,
+    Symbols,
+}
+
+/// Which side of the keys column keys are aligned to.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+enum KeysAlign {
+    #[default]
+    Left,
+    Right,
+}
+
+/// Indentation for saved and exported JSON: a number of spaces or `"tab"`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(untagged)]
+enum JsonIndent {
+    Spaces(usize),
+    Named(String),
+}
+
+impl Default for JsonIndent {
+    fn default() -> Self {
+        Self::Spaces(2)
+    }
+}
+
+impl JsonIndent {
+    /// Returns the indentation string. Unrecognised names fall back to two
+    /// spaces.
+    fn as_str(&self) -> String {
+        match self {
+            Self::Spaces(count) => " ".repeat(*count),
+            Self::Named(name) if name.eq_ignore_ascii_case("tab") => "\t".to_string(),
+            Self::Named(_) => "  ".to_string(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+enum AppSort {
+    /// Alphabetical order.
+    #[default]
+    Alpha,
+    /// Most recently viewed first.
+    Recent,
+}
+
+/// What an unprefixed search matches, set by `search_scope` or
+/// `:searchscope`.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+enum SearchScope {
+    #[default]
+    Both,
+    Keys,
+    Desc,
+}
+
+impl SearchScope {
+    fn parse(name: &str) -> Option<Self> {
+        match name {
+            "both" => Some(Self::Both),
+            "keys" => Some(Self::Keys),
+            "desc" => Some(Self::Desc),
+            _ => None,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Both => "both",
+            Self::Keys => "keys",
+            Self::Desc => "desc",
+        }
+    }
+
+    fn field(self) -> SearchField {
+        match self {
+            Self::Both => SearchField::Any,
+            Self::Keys => SearchField::Keys,
+            Self::Desc => SearchField::Description,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default)]
+struct Config {
+    storage_mode: StorageMode,
+    wrap_descriptions: bool,
+    /// Edit descriptions over several lines, with Shift+Enter for a newline.
+    multiline_descriptions: bool,
+    /// Tighter rows and a smaller font, toggled with `:compact`.
+    compact: bool,
+    /// List search results best match first, toggled with `:relevance`.
+    sort_by_relevance: bool,
+    keybind_notation: KeybindNotation,
+    key_display: KeyDisplay,
+    keys_align: KeysAlign,
+    /// Keys longer than this many characters are shortened in the middle;
+    /// 0 never shortens them.
+    keys_max_width: usize,
+    /// Placed between the chords of a captured key sequence.
+    chord_delimiter: String,
+    /// UI scale factor, changed with Ctrl+=/Ctrl+-/Ctrl+0.
+    zoom: f32,
+    /// Seconds before info and success messages are cleared; 0 keeps them.
+    status_timeout_secs: f32,
+    /// Start with `:hide-empty` in effect.
+    hide_empty: bool,
+    /// Make `ZZ` save every application instead of only the current one.
+    zz_writes_all: bool,
+    /// Ask before `:q!`/`ZQ` throws away unsaved changes.
+    confirm_force_quit: bool,
+    search_scope: SearchScope,
+    /// Show 1-based row numbers in a gutter left of the table.
+    show_line_numbers: bool,
+    /// Number rows by their distance from the selection, like vim's
+    /// `relativenumber`.
+    relative_line_numbers: bool,
+    /// How many undo steps are kept; at least 1.
+    undo_history_size: usize,
+    json_indent: JsonIndent,
+    app_sort: AppSort,
+    /// Sort exported keybinds by keys so exports diff cleanly.
+    export_sorted: bool,
+    /// Paste service that "Share as link" posts the JSON to.
+    #[cfg(feature = "share")]
+    share_endpoint: String,
+    /// Applications listed first in the application filter, toggled with `*`.
+    pinned_apps: Vec<String>,
+    /// The application created on startup when there are none yet.
+    default_application: String,
+    /// Start adding the first keybind right after `:new`.
+    auto_insert_on_new: bool,
+    #[serde(flatten)]
+    keymap: Keymap,
+}
+
+impl Config {
+    /// Whether rows can be taller than one line, so the table can't assume a
+    /// fixed row height.
+    fn variable_row_heights(&self) -> bool {
+        self.wrap_descriptions || self.multiline_descriptions
+    }
+
+    /// Returns a copy with the top-level option `key` parsed from `raw`, which
+    /// is read as a TOML value or else as a string, along with the value as
+    /// it would be saved.
+    fn with_value(&self, key: &str, raw: &str) -> Result<(Config, toml::Value), String> {
+        let mut table = toml::Table::try_from(self).map_err(|e| e.to_string())?;
+        if !table.contains_key(key) {
+            return Err(format!("Unknown option: {}", key));
+        }
+        let value = toml::from_str::<toml::Table>(&format!("value = {}", raw))
+            .ok()
+            .and_then(|mut parsed| parsed.remove("value"))
+            .unwrap_or_else(|| toml::Value::String(raw.to_string()));
+        table.insert(key.to_string(), value);
+        let mut config: Config = table
+            .try_into()
+            .map_err(|e: toml::de::Error| format!("Invalid value for {}: {}", key, e.message()))?;
+        config.sanitize();
+        // Read back so the saved value is the one that took effect.
+        let value = toml::Table::try_from(&config)
+            .ok()
+            .and_then(|mut table| table.remove(key))
+            .ok_or_else(|| format!("Invalid value for {}", key))?;
+        Ok((config, value))
+    }
+
+    /// Brings values that would break the UI back into range, whether they
+    /// came from `config.toml` or `:set`.
+    fn sanitize(&mut self) {
+        // A size of 0 would make undo useless, so at least one step is kept.
+        self.undo_history_size = self.undo_history_size.max(1);
+        self.zoom = if self.zoom.is_finite() {
+            self.zoom.clamp(MIN_ZOOM, MAX_ZOOM)
+        } else {
+            1.0
+        };
+    }
+
+    fn table_row_spacing(&self) -> f32 {
+        if self.compact {
+            COMPACT_ROW_SPACING
+        } else {
+            TABLE_ROW_SPACING
+        }
+    }
+
+    fn table_font(&self) -> egui::FontId {
+        egui::FontId::monospace(if self.compact {
+            COMPACT_FONT_SIZE
+        } else {
+            TABLE_FONT_SIZE
+        })
+    }
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            storage_mode: StorageMode::default(),
+            wrap_descriptions: false,
+            multiline_descriptions: false,
+            compact: false,
+            sort_by_relevance: false,
+            keybind_notation: KeybindNotation::default(),
+            key_display: KeyDisplay::default(),
+            keys_align: KeysAlign::default(),
+            keys_max_width: 0,
+            chord_delimiter: " ".into(),
+            zoom: 1.0,
+            status_timeout_secs: 5.0,
+            hide_empty: false,
+            zz_writes_all: false,
+            confirm_force_quit: false,
+            search_scope: SearchScope::default(),
+            show_line_numbers: false,
+            relative_line_numbers: false,
+            undo_history_size: DEFAULT_UNDO_HISTORY_SIZE,
+            json_indent: JsonIndent::default(),
+            app_sort: AppSort::default(),
+            export_sorted: false,
+            #[cfg(feature = "share")]
+            share_endpoint: DEFAULT_SHARE_ENDPOINT.into(),
+            pinned_apps: Vec::new(),
+            default_application: "default".into(),
+            auto_insert_on_new: false,
+            keymap: Keymap::default(),
+        }
+    }
+}
+
+/// The part of a keybind a search is scoped to with a `keys:`, `desc:` or
+/// `app:` prefix.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SearchField {
+    Any,
+    Keys,
+    Description,
+    App,
+}
+
+/// Splits a search query into its field prefix and lowercase terms, which
+/// must all match.
+fn parse_search_query(query: &str) -> (SearchField, Vec<String>) {
+    let query = query.trim_start();
+    let (field, rest) = [
+        ("keys:", SearchField::Keys),
+        ("desc:", SearchField::Description),
+        ("app:", SearchField::App),
+    ]
+    .into_iter()
+    .find_map(|(prefix, field)| query.strip_prefix(prefix).map(|rest| (field, rest)))
+    .unwrap_or((SearchField::Any, query));
+    let terms = rest
+        .split_whitespace()
+        .map(|term| term.to_lowercase())
+        .collect();
+    (field, terms)
+}
+
+/// Fuzzy-matches every term against `text` on its own, returning the sum of
+/// their scores and the union of their match indices if all of them match.
+fn fuzzy_match_all(
+    matcher: &SkimMatcherV2,
+    text: &str,
+    terms: &[String],
+) -> Option<(i64, Vec<usize>)> {
+    let mut score = 0;
+    let mut indices = Vec::new();
+    for term in terms {
+        let (term_score, term_indices) = matcher.fuzzy_indices(text, term)?;
+        score += term_score;
+        indices.extend(term_indices);
+    }
+    indices.sort_unstable();
+    indices.dedup();
+    Some((score, indices))
+}
+
+/// Restricts the table to keybinds using a given modifier, set by `:only`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ModifierFilter {
+    Ctrl,
+    Alt,
+    Shift,
+    /// Keybinds without any modifier.
+    None,
+}
+
+impl ModifierFilter {
+    fn parse(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "ctrl" | "control" => Some(Self::Ctrl),
+            "alt" | "meta" | "option" => Some(Self::Alt),
+            "shift" => Some(Self::Shift),
+            "none" => Some(Self::None),
+            _ => None,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Ctrl => "ctrl",
+            Self::Alt => "alt",
+            Self::Shift => "shift",
+            Self::None => "none",
+        }
+    }
+
+    fn matches(self, keys: &str) -> bool {
+        let (ctrl, alt, shift) = key_modifiers(keys);
+        match self {
+            Self::Ctrl => ctrl,
+            Self::Alt => alt,
+            Self::Shift => shift,
+            Self::None => !ctrl && !alt && !shift,
+        }
+    }
+}
+
+/// The modifier a `Ctrl+Shift+T` style name stands for, if it is one.
+fn modifier_name(part: &str) -> Option<&'static str> {
+    match part.to_lowercase().as_str() {
+        "ctrl" | "control" | "ctl" => Some("Ctrl"),
+        "alt" | "meta" | "option" | "opt" => Some("Alt"),
+        "shift" => Some("Shift"),
+        "cmd" | "command" | "super" => Some("Cmd"),
+        _ => None,
+    }
+}
+
+/// Flags keys that can't be pressed as written and are likely typos: a
+/// chord of two keys such as `A+B`, a repeated modifier such as
+/// `Ctrl+Ctrl+A` or a trailing `+` with no key. Chords are split on
+/// whitespace and on `delimiter`, the configured `chord_delimiter`. Only
+/// `+`-joined chords are checked; anything else is free text.
+fn validate_keybind(keys: &str, delimiter: &str) -> Result<(), String> {
+    let delimiter = delimiter.trim();
+    let chords = keys.split_whitespace().flat_map(|word| {
+        if delimiter.is_empty() {
+            vec![word]
+        } else {
+            word.split(delimiter).collect()
+        }
+    });
+    for chord in chords {
+        if chord == "+" || !chord.contains('+') {
+            continue;
+        }
+        // `Ctrl++` is Ctrl and the plus key.
+        let (body, plus_key) = match chord.strip_suffix("++") {
+            Some(body) => (body, true),
+            None => (chord, false),
+        };
+        if !plus_key && body.ends_with('+') {
+            return Err(format!("'{}' ends with + but no key", chord));
+        }
+        let mut modifiers = HashSet::new();
+        let mut pressed = Vec::new();
+        for part in body.split('+').filter(|part| !part.is_empty()) {
+            match modifier_name(part) {
+                Some(modifier) if !modifiers.insert(modifier) => {
+                    return Err(format!("'{}' repeats {}", chord, modifier));
+                }
+                Some(_) => {}
+                None => pressed.push(part),
+            }
+        }
+        if plus_key {
+            pressed.push("+");
+        }
+        if pressed.len() > 1 {
+            return Err(format!(
+                "'{}' combines the keys {}",
+                chord,
+                pressed.join(" and ")
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Sums up the keybinds among `entries` that `validate_keybind` flags, for
+/// the status bar after an import.
+fn malformed_keys_warning<'a>(
+    entries: impl IntoIterator<Item = &'a KeybindEntry>,
+    delimiter: &str,
+) -> Option<String> {
+    let errors: Vec<String> = entries
+        .into_iter()
+        .filter(|entry| entry.kind == RowKind::Keybind)
+        .filter_map(|entry| validate_keybind(&entry.keys, delimiter).err())
+        .collect();
+    let first = errors.first()?;
+    Some(format!(
+        "{} keybind(s) look mistyped, e.g. {}",
+        errors.len(),
+        first
+    ))
+}
+
+/// Returns which of Ctrl, Alt and Shift appear in a keys string. Both
+/// `Ctrl+Shift+T` style names and `C-`/`M-`/`S-` prefixes are recognised.
+fn key_modifiers(keys: &str) -> (bool, bool, bool) {
+    let (mut ctrl, mut alt, mut shift) = (false, false, false);
+    for chord in keys.split_whitespace() {
+        let chord = chord.trim_start_matches('<').trim_end_matches('>');
+        let mut rest = chord;
+        while rest.len() > 2 && rest.as_bytes()[1] == b'-' {
+            match rest.as_bytes()[0] {
+                b'C' => ctrl = true,
+                b'M' | b'A' => alt = true,
+                b'S' => shift = true,
+                _ => break,
+            }
+            rest = &rest[2..];
+        }
+        let mut parts: Vec<_> = rest.split('+').collect();
+        // The last part is the key itself, which may be `+`.
+        parts.pop();
+        for part in parts {
+            match modifier_name(part) {
+                Some("Ctrl") => ctrl = true,
+                Some("Alt") => alt = true,
+                Some("Shift") => shift = true,
+                _ => {}
+            }
+        }
+    }
+    (ctrl, alt, shift)
+}
+
 // This function correctly maps a string from config to an egui::Key
 fn string_to_key(s: &str) -> Option<Key> {
     Some(match s.to_uppercase().as_str() {
@@ -160,52 +954,325 @@ fn string_to_key(s: &str) -> Option<Key> {
         "SLASH" => Key::Slash,
         "COLON" => Key::Colon,
         "SEMICOLON" => Key::Semicolon,
-        _ => return None,
+        // egui reports numpad keys as their main keyboard equivalents.
+        "NUMPADENTER" => Key::Enter,
+        "NUMPADADD" => Key::Plus,
+        "NUMPADSUBTRACT" => Key::Minus,
+        "NUMPADDIVIDE" => Key::Slash,
+        "NUMPADDECIMAL" => Key::Period,
+        // Everything else egui has a name for, e.g. `Num5`, `Numpad5`, `5`,
+        // `Minus` or `OpenBracket`.
+        _ => return Key::from_name(s).or_else(|| Key::from_name(s.strip_prefix("Num")?)),
     })
 }
 
+/// How a status message is colored. Info and success messages are cleared
+/// after `status_timeout_secs`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Severity {
+    Info,
+    Success,
+    Warning,
+    Error,
+}
+
+impl Severity {
+    fn color(self, visuals: &egui::Visuals) -> Color32 {
+        match self {
+            Severity::Info => visuals.text_color(),
+            Severity::Success => Color32::LIGHT_GREEN,
+            Severity::Warning => visuals.warn_fg_color,
+            Severity::Error => visuals.error_fg_color,
+        }
+    }
+}
+
 struct AppState {
     keybinds: Vec<Keybind>,
     all_applications: HashSet<String>,
+    app_notes: HashMap<String, String>,
+    app_colors: HashMap<String, [u8; 3]>,
+    /// Ids of the rows added or changed since they were loaded or saved.
+    modified_this_session: HashSet<u64>,
+    next_keybind_id: u64,
+    /// Applications removed by `:merge` or by undoing `:new`, whose saved
+    /// copy is deleted at the next `:wa` unless they're back by then.
+    removed_apps: HashSet<String>,
+    /// The selected cell of each application when it was last switched away
+    /// from.
+    app_selections: HashMap<String, (usize, usize)>,
+    app_last_viewed: HashMap<String, u64>,
+    checked_apps: HashSet<String>,
+    modifier_filter: Option<ModifierFilter>,
+    /// The labelled rows while jumping, as drawn in the last frame.
+    jump_targets: Vec<(String, usize)>,
+    /// The first letter typed of a two-letter jump label.
+    jump_prefix: Option<char>,
+    /// `:starred`: list starred keybinds from every application.
+    starred_view: bool,
+    captured_keys: Option<String>,
+    capturing_sequence: bool,
+    pending_import: Option<(AppKeybinds, bool)>,
+    import_match_index: usize,
+    dragging_row: Option<usize>,
+    drag_target: Option<usize>,
     filtered_items: Vec<FilteredItem>,
     selected_cell: (usize, usize),
+    visible_rows: usize,
+    /// The row the table last scrolled to. Cleared when the rows change, so
+    /// the selection is brought back into view even if its index didn't move.
+    last_scrolled_row: Option<usize>,
+    table_scroll_offset: f32,
+    table_viewport_height: f32,
     mode: Mode,
     search_query: String,
     command_buffer: String,
     status_message: String,
+    status_severity: Severity,
+    status_set_at: Instant,
     current_application: String,
     app_search_query: String,
     temp_edit_buffer: String,
     is_listening_for_keybind: bool,
     should_quit: bool,
-    undo_history: Vec<Vec<Keybind>>,
+    /// The applications listed by the `:q` confirmation popup.
+    unsaved_on_quit: Vec<String>,
+    undo_history: Vec<UndoEntry>,
+    message_history: VecDeque<(Instant, Severity, String)>,
+    diff_lines: Vec<DiffLine>,
+    /// `:grep` matches as indices into `keybinds`, and the pattern searched.
+    grep_results: Vec<usize>,
+    grep_pattern: String,
+    grep_selected_index: usize,
+    /// Data files skipped by the last load.
+    load_failures: LoadFailures,
+    history_selected_index: usize,
+    palette_query: String,
+    help_query: String,
+    help_scroll_offset: f32,
+    help_viewport_height: f32,
+    palette_selected_index: usize,
     ignore_next_input_frame: bool,
     app_filter_selected_index: usize,
     leader_key_pressed: bool,
     delete_leader_pressed: bool,
+    yank_leader_pressed: bool,
+    quit_leader_pressed: bool,
+    /// `q` was pressed and the next key names the register to record into.
+    record_macro_pressed: bool,
+    /// `@` was pressed and the next key names the register to replay.
+    play_macro_pressed: bool,
+    /// The register being recorded into, if any.
+    recording_macro: Option<char>,
+    /// Key presses recorded so far into `recording_macro`.
+    macro_buffer: Vec<egui::Event>,
+    /// Recorded Normal mode key presses by register.
+    macros: HashMap<char, Vec<egui::Event>>,
+    /// Set by `@<reg>`; the macro is replayed once the frame's keys are handled.
+    pending_macro: Option<char>,
+    replaying_macro: bool,
+    clipboard_text: Option<String>,
+    awaiting_paste: bool,
+    /// Kept open because on X11 the copied text is only served while the
+    /// clipboard is alive.
+    #[cfg(feature = "system-clipboard")]
+    system_clipboard: Option<arboard::Clipboard>,
+    /// The application being shared and the upload's result, once `curl`
+    /// answers.
+    #[cfg(feature = "share")]
+    share_upload: Option<(String, std::sync::mpsc::Receiver<Result<String, String>>)>,
     just_created_new_keybind: bool,
+    new_keybind_index: Option<usize>,
+    hide_empty: bool,
+    search_scope: SearchScope,
+    /// Cleared by `:nohl` and set again by the next search.
+    highlight_enabled: bool,
     dirty: bool,
+    locked: bool,
+    /// Set by `--app` or `:only-app` to show one application as a dedicated
+    /// cheatsheet, with app switching disabled.
+    single_app: bool,
     debug_mode: bool,
-    keymap: Keymap,
+    config: Config,
 }
 
 fn get_config_dir() -> PathBuf {
     if let Some(proj_dirs) = ProjectDirs::from("com", "ctrlset", "ctrlset") {
         proj_dirs.config_dir().to_path_buf()
     } else {
-        PathBuf::from(".")
+        fallback_dir("XDG_CONFIG_HOME")
     }
 }
 
+/// Used when the platform directories can't be found, usually because there
+/// is no home directory: `$<xdg_var>/ctrlset` if that's set to an absolute
+/// path, else the current directory.
+fn fallback_dir(xdg_var: &str) -> PathBuf {
+    std::env::var_os(xdg_var)
+        .map(PathBuf::from)
+        .filter(|dir| dir.is_absolute())
+        .map(|dir| dir.join("ctrlset"))
+        .unwrap_or_else(|| PathBuf::from("."))
+}
+
+/// A warning naming where the config and keybinds are kept if the platform
+/// directories couldn't be found.
+fn directory_fallback_warning() -> Option<String> {
+    if ProjectDirs::from("com", "ctrlset", "ctrlset").is_some() {
+        return None;
+    }
+    let in_cwd = |dir: &Path| dir == Path::new(".");
+    let (config_dir, data_dir) = (get_config_dir(), get_data_dir());
+    let cwd = std::env::current_dir().unwrap_or_default();
+    let show = |dir: &Path| {
+        if in_cwd(dir) {
+            format!("the current directory ({})", cwd.display())
+        } else {
+            dir.display().to_string()
+        }
+    };
+    Some(format!(
+        "No home directory found: config is in {} and keybinds in {}. Set {} to choose where keybinds go.",
+        show(&config_dir),
+        show(&data_dir),
+        DATA_DIR_ENV
+    ))
+}
+
 fn get_data_dir() -> PathBuf {
+    if let Some(dir) = DATA_DIR_OVERRIDE.get() {
+        return dir.clone();
+    }
+    if let Some(dir) = std::env::var_os(DATA_DIR_ENV).filter(|dir| !dir.is_empty()) {
+        return PathBuf::from(dir);
+    }
     if let Some(proj_dirs) = ProjectDirs::from("com", "ctrlset", "ctrlset") {
         proj_dirs.data_dir().to_path_buf()
     } else {
-        PathBuf::from(".")
+        fallback_dir("XDG_DATA_HOME")
+    }
+}
+
+/// Opens `path` with the system's default handler: a folder in the file
+/// manager, a file in the application registered for it.
+fn open_with_system(path: &Path) -> std::io::Result<()> {
+    let program = if cfg!(target_os = "windows") {
+        "explorer"
+    } else if cfg!(target_os = "macos") {
+        "open"
+    } else {
+        "xdg-open"
+    };
+    std::process::Command::new(program)
+        .arg(path)
+        .spawn()
+        .map(|_| ())
+}
+
+/// Posts `body` to a paste service with the system `curl`, which keeps an
+/// HTTP and TLS stack out of the build, and returns the URL it answers with.
+#[cfg(feature = "share")]
+fn post_paste(endpoint: &str, body: &str) -> Result<String, String> {
+    use std::io::Write;
+    use std::process::{Command, Stdio};
+
+    let mut child = Command::new("curl")
+        .args([
+            "--silent",
+            "--show-error",
+            "--fail",
+            "--max-time",
+            "15",
+            "--data-binary",
+            "@-",
+            endpoint,
+        ])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("couldn't run curl: {}", e))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin
+            .write_all(body.as_bytes())
+            .map_err(|e| format!("couldn't send the keybinds: {}", e))?;
+    }
+    let output = child
+        .wait_with_output()
+        .map_err(|e| format!("curl failed: {}", e))?;
+    if !output.status.success() {
+        let error = String::from_utf8_lossy(&output.stderr);
+        return Err(error.trim().trim_start_matches("curl: ").to_string());
+    }
+    let url = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    if url.starts_with("http://") || url.starts_with("https://") {
+        Ok(url)
+    } else {
+        Err(format!("{} didn't answer with a link", endpoint))
+    }
+}
+
+/// Whether a clipboard can exist at all. egui's clipboard fails silently
+/// without a display server, for example over SSH or in a headless session.
+fn clipboard_available() -> bool {
+    if cfg!(any(target_os = "windows", target_os = "macos")) {
+        return true;
+    }
+    std::env::var_os("WAYLAND_DISPLAY").is_some() || std::env::var_os("DISPLAY").is_some()
+}
+
+/// Parses a `#rrggbb` color; the `#` is optional.
+fn parse_hex_color(text: &str) -> Option<[u8; 3]> {
+    let hex = text.strip_prefix('#').unwrap_or(text);
+    if hex.len() != 6 || !hex.is_ascii() {
+        return None;
+    }
+    let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+    Some([channel(0)?, channel(2)?, channel(4)?])
+}
+
+/// Splits command arguments on whitespace, keeping "double quoted" parts
+/// together.
+fn split_quoted(text: &str) -> Vec<String> {
+    let mut args = Vec::new();
+    let mut current = String::new();
+    let mut in_quotes = false;
+    let mut has_arg = false;
+    for c in text.chars() {
+        match c {
+            '"' => {
+                in_quotes = !in_quotes;
+                has_arg = true;
+            }
+            c if c.is_whitespace() && !in_quotes => {
+                if has_arg {
+                    args.push(std::mem::take(&mut current));
+                    has_arg = false;
+                }
+            }
+            c => {
+                current.push(c);
+                has_arg = true;
+            }
+        }
+    }
+    if has_arg {
+        args.push(current);
+    }
+    args
+}
+
+/// Expands a leading `~` to the user's home directory.
+fn expand_home(path: &str) -> PathBuf {
+    if let Some(rest) = path.strip_prefix("~/") {
+        if let Some(base_dirs) = BaseDirs::new() {
+            return base_dirs.home_dir().join(rest);
+        }
     }
+    PathBuf::from(path)
 }
 
-fn load_or_create_config() -> Keymap {
+fn load_or_create_config() -> Config {
     let config_dir = get_config_dir();
     if !config_dir.exists() {
         fs::create_dir_all(&config_dir)
@@ -214,60 +1281,249 @@ fn load_or_create_config() -> Keymap {
     let config_path = config_dir.join("config.toml");
 
     if !config_path.exists() {
-        let default_keymap = Keymap::default();
+        let default_config = Config::default();
         let toml_string =
-            toml::to_string_pretty(&default_keymap).expect("Could not serialize default keymap");
+            toml::to_string_pretty(&default_config).expect("Could not serialize default config");
         fs::write(&config_path, toml_string)
             .unwrap_or_else(|e| eprintln!("Failed to write default config: {}", e));
-        return default_keymap;
+        return default_config;
     }
 
-    let toml_string = fs::read_to_string(config_path).unwrap_or_default();
-    toml::from_str(&toml_string).unwrap_or_else(|e| {
-        eprintln!("Failed to parse config.toml, using defaults. Error: {}", e);
-        Keymap::default()
+    read_config(&config_path).unwrap_or_else(|e| {
+        eprintln!("{}, using defaults.", e);
+        Config::default()
     })
 }
 
+fn read_config(path: &Path) -> Result<Config, String> {
+    let toml_string = fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let mut config: Config = toml::from_str(&toml_string)
+        .map_err(|e| format!("Failed to parse config.toml: {}", e.message()))?;
+    config.sanitize();
+    Ok(config)
+}
+
+/// Sets a single top-level `key` in the config file at `path`, leaving the
+/// rest of the file's settings as they are. Comments and formatting are lost,
+/// since the whole table is written back.
+fn write_config_value(path: &Path, key: &str, value: toml::Value) -> Result<(), String> {
+    let mut table: toml::Table = match fs::read_to_string(path) {
+        Ok(toml_string) => toml::from_str(&toml_string)
+            .map_err(|e| format!("Failed to parse config.toml: {}", e.message()))?,
+        Err(_) => toml::Table::new(),
+    };
+    table.insert(key.to_string(), value);
+    let toml_string =
+        toml::to_string_pretty(&table).map_err(|e| format!("Failed to serialize config: {}", e))?;
+    fs::write(path, toml_string).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+/// The per-app file an application is saved to. Characters that aren't
+/// allowed in file names on some platform become `_`; the real name is kept
+/// inside the file.
+fn app_file_name(app_name: &str) -> String {
+    let stem: String = app_name
+        .chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect();
+    // Windows drops trailing dots and spaces.
+    let mut stem = stem.trim_end_matches(['.', ' ']).to_string();
+    if stem.is_empty() || stem == "." {
+        stem = "_".to_string();
+    }
+    let file_name = format!("{}.json", stem);
+    // Case-insensitive file systems would treat `Keybinds.json` as the same.
+    if file_name.to_lowercase() == SINGLE_FILE_NAME {
+        format!("{}_.json", stem)
+    } else {
+        file_name
+    }
+}
+
+/// Reads every per-application JSON file in `dir`, skipping the combined file.
+/// Files that can't be read or parsed are returned separately with the error.
+fn read_per_app_files(dir: &Path) -> Result<PerAppFiles, String> {
+    let entries = fs::read_dir(dir).map_err(|_| "Error reading keybinds directory.".to_string())?;
+    let mut apps = Vec::new();
+    let mut failed = Vec::new();
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if !path.is_file()
+            || path.extension().and_then(|s| s.to_str()) != Some("json")
+            || path.file_name().and_then(|s| s.to_str()) == Some(SINGLE_FILE_NAME)
+        {
+            continue;
+        }
+        match fs::read_to_string(&path) {
+            Ok(data) => match serde_json::from_str::<AppKeybinds>(&data) {
+                Ok(app_keybinds) => apps.push((path, app_keybinds)),
+                Err(e) => failed.push((path, e.to_string())),
+            },
+            Err(e) => failed.push((path, e.to_string())),
+        }
+    }
+    Ok((apps, failed))
+}
+
+/// Reads the combined keybinds file. A missing file is treated as empty.
+fn read_single_file(path: &Path) -> Result<Vec<AppKeybinds>, String> {
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let data =
+        fs::read_to_string(path).map_err(|e| format!("Error reading {}: {}", path.display(), e))?;
+    serde_json::from_str(&data).map_err(|e| format!("Error parsing {}: {}", path.display(), e))
+}
+
+/// Reads when each application was last viewed, in seconds since the Unix
+/// epoch. A missing or unreadable file means nothing was viewed yet.
+fn read_recent_apps(path: &Path) -> HashMap<String, u64> {
+    fs::read_to_string(path)
+        .ok()
+        .and_then(|data| serde_json::from_str(&data).ok())
+        .unwrap_or_default()
+}
+
+/// Pretty-prints `value` as JSON using the configured indentation.
+fn to_json<T: Serialize + ?Sized>(value: &T, indent: &JsonIndent) -> serde_json::Result<String> {
+    let indent = indent.as_str();
+    let formatter = serde_json::ser::PrettyFormatter::with_indent(indent.as_bytes());
+    let mut buffer = Vec::new();
+    let mut serializer = serde_json::Serializer::with_formatter(&mut buffer, formatter);
+    value.serialize(&mut serializer)?;
+    // The serializer only ever writes valid UTF-8.
+    Ok(String::from_utf8(buffer).expect("serde_json produced invalid UTF-8"))
+}
+
+/// Sorts keybinds by keys, then description. Headers stay in place and the
+/// keybinds under each one are sorted on their own.
+fn sort_entries_by_keys(entries: &mut [KeybindEntry]) {
+    for section in entries.split_mut(|entry| entry.kind == RowKind::Header) {
+        section.sort_by(|a, b| (&a.keys, &a.description).cmp(&(&b.keys, &b.description)));
+    }
+}
+
+fn write_single_file(path: &Path, apps: &[AppKeybinds], indent: &JsonIndent) -> Result<(), String> {
+    let json =
+        to_json(apps, indent).map_err(|_| "Error: Failed to serialize keybinds.".to_string())?;
+    fs::write(path, json).map_err(|_| format!("Error: Failed to write to {}.", path.display()))
+}
+
 impl AppState {
     fn new(debug_mode: bool) -> Self {
-        let keymap = load_or_create_config();
-        let mut app = Self {
+        let mut app = Self::with_config(load_or_create_config(), debug_mode);
+        app.app_last_viewed = read_recent_apps(&get_config_dir().join(RECENT_FILE_NAME));
+        app.load_all_keybinds();
+        let default_application = match app.config.default_application.trim() {
+            "" => "default".to_string(),
+            name => name.to_string(),
+        };
+        app.current_application = app
+            .sorted_applications()
+            .first()
+            .cloned()
+            .unwrap_or(default_application);
+        if !app.all_applications.contains(&app.current_application) {
+            app.all_applications.insert(app.current_application.clone());
+        }
+        app.mark_app_viewed();
+        app.refilter();
+        app.report_unknown_keys();
+        if let Some(warning) = directory_fallback_warning() {
+            app.set_status(Severity::Warning, warning);
+        }
+        app
+    }
+
+    /// Builds an empty state around `config` without touching the disk.
+    fn with_config(config: Config, debug_mode: bool) -> Self {
+        Self {
             keybinds: vec![],
             all_applications: HashSet::new(),
+            app_notes: HashMap::new(),
+            app_colors: HashMap::new(),
+            app_selections: HashMap::new(),
+            modified_this_session: HashSet::new(),
+            next_keybind_id: 0,
+            removed_apps: HashSet::new(),
+            app_last_viewed: HashMap::new(),
+            checked_apps: HashSet::new(),
+            modifier_filter: None,
+            jump_targets: Vec::new(),
+            jump_prefix: None,
+            starred_view: false,
+            captured_keys: None,
+            capturing_sequence: false,
+            pending_import: None,
+            import_match_index: 0,
+            dragging_row: None,
+            drag_target: None,
             filtered_items: vec![],
             selected_cell: (0, 0),
+            visible_rows: 1,
+            last_scrolled_row: None,
+            table_scroll_offset: 0.0,
+            table_viewport_height: 0.0,
             mode: Mode::Normal,
             search_query: String::new(),
             command_buffer: String::new(),
             status_message: "Welcome to ctrlset!".to_string(),
+            status_severity: Severity::Info,
+            status_set_at: Instant::now(),
             current_application: String::new(),
             app_search_query: String::new(),
             temp_edit_buffer: String::new(),
             is_listening_for_keybind: false,
             should_quit: false,
+            unsaved_on_quit: Vec::new(),
             undo_history: Vec::new(),
+            message_history: VecDeque::new(),
+            diff_lines: Vec::new(),
+            grep_results: Vec::new(),
+            grep_pattern: String::new(),
+            grep_selected_index: 0,
+            load_failures: Vec::new(),
+            history_selected_index: 0,
+            palette_query: String::new(),
+            help_query: String::new(),
+            help_scroll_offset: 0.0,
+            help_viewport_height: 0.0,
+            palette_selected_index: 0,
             ignore_next_input_frame: false,
             app_filter_selected_index: 0,
             leader_key_pressed: false,
             delete_leader_pressed: false,
+            yank_leader_pressed: false,
+            quit_leader_pressed: false,
+            record_macro_pressed: false,
+            play_macro_pressed: false,
+            recording_macro: None,
+            macro_buffer: Vec::new(),
+            macros: HashMap::new(),
+            pending_macro: None,
+            replaying_macro: false,
+            clipboard_text: None,
+            awaiting_paste: false,
+            #[cfg(feature = "system-clipboard")]
+            system_clipboard: None,
+            #[cfg(feature = "share")]
+            share_upload: None,
             just_created_new_keybind: false,
+            new_keybind_index: None,
+            hide_empty: config.hide_empty,
+            search_scope: config.search_scope,
+            highlight_enabled: true,
             dirty: false,
+            locked: false,
+            single_app: false,
             debug_mode,
-            keymap,
-        };
-        app.load_all_keybinds();
-        let mut apps: Vec<_> = app.all_applications.iter().cloned().collect();
-        apps.sort();
-        app.current_application = apps
-            .get(0)
-            .cloned()
-            .unwrap_or_else(|| "default".to_string());
-        if !app.all_applications.contains(&app.current_application) {
-            app.all_applications.insert(app.current_application.clone());
+            config,
         }
-        app.refilter();
-        app
     }
 
     fn get_all_applications(&self) -> Vec<String> {
@@ -276,142 +1532,891 @@ impl AppState {
         apps
     }
 
-    fn save_current_app_keybinds(&mut self) {
-        let dir = get_data_dir();
-        if !dir.exists() {
-            if let Err(e) = fs::create_dir_all(&dir) {
-                self.status_message = format!("Error creating directory: {}", e);
-                return;
-            }
-        }
-
-        let app_name = &self.current_application;
-        let path = dir.join(format!("{}.json", app_name));
-
-        let entries: Vec<KeybindEntry> = self
-            .keybinds
-            .iter()
-            .filter(|kb| &kb.application == app_name)
-            .map(|kb| KeybindEntry {
-                keys: kb.keys.clone(),
-                description: kb.description.clone(),
-            })
-            .collect();
+    /// Returns the applications in the order lists should show them: pinned
+    /// ones first, each group ordered by `app_sort`.
+    fn listed_applications(&self) -> Vec<String> {
+        let mut apps = self.sorted_applications();
+        // Stable, so both groups keep their order.
+        apps.sort_by_key(|app| !self.config.pinned_apps.contains(app));
+        apps
+    }
 
-        let app_keybinds = AppKeybinds {
-            application: app_name.clone(),
-            keybinds: entries,
-        };
+    /// Returns the applications ordered by `app_sort`.
+    fn sorted_applications(&self) -> Vec<String> {
+        let mut apps = self.get_all_applications();
+        if self.config.app_sort == AppSort::Recent {
+            // Stable, so never-viewed apps stay alphabetical at the end.
+            apps.sort_by_key(|app| {
+                std::cmp::Reverse(self.app_last_viewed.get(app).copied().unwrap_or(0))
+            });
+        }
+        apps
+    }
 
-        match serde_json::to_string_pretty(&app_keybinds) {
-            Ok(json) => {
-                if fs::write(&path, json).is_ok() {
-                    self.status_message = format!("Saved {} successfully.", app_name);
-                    self.dirty = false;
-                } else {
-                    self.status_message = format!("Error: Failed to write to {}.", path.display());
-                }
+    /// Pins `app` to the top of the application filter, or unpins it, and
+    /// saves the pins to `config.toml`.
+    fn toggle_pinned(&mut self, app: &str) {
+        let pinned = &mut self.config.pinned_apps;
+        let now_pinned = match pinned.iter().position(|name| name == app) {
+            Some(pos) => {
+                pinned.remove(pos);
+                false
             }
-            Err(_) => {
-                self.status_message = "Error: Failed to serialize keybinds.".to_string();
+            None => {
+                pinned.push(app.to_string());
+                true
             }
+        };
+        let value = toml::Value::Array(
+            pinned
+                .iter()
+                .map(|name| toml::Value::String(name.clone()))
+                .collect(),
+        );
+        let path = get_config_dir().join("config.toml");
+        match write_config_value(&path, "pinned_apps", value) {
+            Ok(()) if now_pinned => self.set_status(Severity::Info, format!("Pinned {}.", app)),
+            Ok(()) => self.set_status(Severity::Info, format!("Unpinned {}.", app)),
+            Err(e) => self.set_status(Severity::Error, format!("Error: {}", e)),
+        }
+    }
+
+    /// Records that the current application was just viewed.
+    fn mark_app_viewed(&mut self) {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        self.app_last_viewed
+            .insert(self.current_application.clone(), now);
+        // Losing the recency order isn't worth interrupting the user over.
+        if let Ok(json) = serde_json::to_string(&self.app_last_viewed) {
+            let _ = fs::write(get_config_dir().join(RECENT_FILE_NAME), json);
+        }
+    }
+
+    /// Moves filtered row `from` so it sits before filtered row `to`, or after
+    /// the last row when `to` is the row count.
+    fn move_filtered_row(&mut self, from: usize, to: usize) {
+        if to == from || to == from + 1 {
+            return;
+        }
+        let Some(source) = self
+            .filtered_items
+            .get(from)
+            .map(|item| item.original_index)
+        else {
+            return;
+        };
+        let mut dest = match self.filtered_items.get(to) {
+            Some(item) => item.original_index,
+            None => match self.filtered_items.last() {
+                Some(item) => item.original_index + 1,
+                None => return,
+            },
+        };
+
+        self.push_to_undo_history("Reorder keybind");
+        let keybind = self.keybinds.remove(source);
+        if source < dest {
+            dest -= 1;
+        }
+        self.modified_this_session.insert(keybind.id);
+        self.keybinds.insert(dest, keybind);
+        self.refilter();
+        self.selected_cell.0 = if to > from { to - 1 } else { to };
+        self.set_status(Severity::Success, "Keybind moved.");
+    }
+
+    /// Serializes `app` for an export, sorted by keys with `export_sorted`.
+    fn export_json(&self, mut app: AppKeybinds) -> serde_json::Result<String> {
+        if self.config.export_sorted {
+            sort_entries_by_keys(&mut app.keybinds);
+        }
+        to_json(&app, &self.config.json_indent)
+    }
+
+    /// Starts uploading the current application's JSON to `share_endpoint`
+    /// on another thread, so the window keeps drawing while `curl` runs.
+    #[cfg(feature = "share")]
+    fn share_current_app(&mut self) {
+        if self.share_upload.is_some() {
+            self.set_status(Severity::Warning, "Already uploading; wait for the link.");
+            return;
+        }
+        let json = match self.export_json(self.app_keybinds(&self.current_application)) {
+            Ok(json) => json,
+            Err(_) => {
+                self.set_status(Severity::Error, "Error: Failed to serialize keybinds.");
+                return;
+            }
+        };
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let endpoint = self.config.share_endpoint.clone();
+        std::thread::spawn(move || {
+            let _ = sender.send(post_paste(&endpoint, &json));
+        });
+        self.share_upload = Some((self.current_application.clone(), receiver));
+        self.set_status(
+            Severity::Info,
+            format!("Uploading {}…", self.current_application),
+        );
+    }
+
+    /// Checks on the upload started by [`Self::share_current_app`] and copies
+    /// the link once it's there.
+    #[cfg(feature = "share")]
+    fn poll_share_upload(&mut self, ctx: &Context) {
+        use std::sync::mpsc::TryRecvError;
+
+        let Some((app_name, receiver)) = &self.share_upload else {
+            return;
+        };
+        let result = match receiver.try_recv() {
+            Ok(result) => result,
+            Err(TryRecvError::Empty) => {
+                ctx.request_repaint_after(std::time::Duration::from_millis(100));
+                return;
+            }
+            Err(TryRecvError::Disconnected) => Err("the upload stopped unexpectedly".into()),
+        };
+        let app_name = app_name.clone();
+        self.share_upload = None;
+        match result {
+            Ok(url) => {
+                self.set_status(
+                    Severity::Success,
+                    format!("Shared {} at {} (link copied).", app_name, url),
+                );
+                self.clipboard_text = Some(url);
+            }
+            Err(e) => self.set_status(Severity::Error, format!("Sharing failed: {}", e)),
+        }
+    }
+
+    /// Writes each of `app_names` to its own JSON file in `folder`.
+    fn export_apps(&mut self, app_names: &[String], folder: &Path) {
+        self.set_status(
+            Severity::Success,
+            format!("Exported {} application(s).", app_names.len()),
+        );
+        for app_name in app_names {
+            if let Ok(json) = self.export_json(self.app_keybinds(app_name)) {
+                let path = folder.join(app_file_name(app_name));
+                if fs::write(path, json).is_err() {
+                    self.set_status(
+                        Severity::Error,
+                        format!("Error writing file for {}.", app_name),
+                    );
+                    break;
+                }
+            }
+        }
+    }
+
+    /// Returns the rows currently shown in the table as the current
+    /// application's keybinds.
+    fn filtered_app_keybinds(&self) -> AppKeybinds {
+        AppKeybinds {
+            application: self.current_application.clone(),
+            notes: self
+                .app_notes
+                .get(&self.current_application)
+                .cloned()
+                .unwrap_or_default(),
+            color: self.app_colors.get(&self.current_application).copied(),
+            keybinds: self
+                .filtered_items
+                .iter()
+                .map(|item| &self.keybinds[item.original_index])
+                .filter(|kb| kb.application == self.current_application)
+                .map(|kb| KeybindEntry {
+                    keys: kb.keys.clone(),
+                    description: kb.description.clone(),
+                    uses: kb.uses,
+                    kind: kb.kind,
+                    enabled: kb.enabled,
+                    starred: kb.starred,
+                    id: kb.id,
+                })
+                .collect(),
+        }
+    }
+
+    /// Makes `app_name` the current application, remembering the selection in
+    /// the one being left and restoring where it was last left in `app_name`.
+    fn switch_to_app(&mut self, app_name: String) {
+        if app_name != self.current_application {
+            self.app_selections
+                .insert(self.current_application.clone(), self.selected_cell);
+            self.current_application = app_name;
+            self.selected_cell = self
+                .app_selections
+                .get(&self.current_application)
+                .copied()
+                .unwrap_or((0, 0));
+            self.mark_app_viewed();
+        }
+        self.refilter();
+    }
+
+    /// Switches to the application `step` places away in alphabetical order,
+    /// wrapping around at either end.
+    fn cycle_application(&mut self, step: isize) {
+        if self.is_single_app() {
+            return;
+        }
+        let apps = self.get_all_applications();
+        if apps.is_empty() {
+            return;
+        }
+        let current = apps
+            .iter()
+            .position(|app| *app == self.current_application)
+            .unwrap_or(0) as isize;
+        let next = (current + step).rem_euclid(apps.len() as isize) as usize;
+        self.switch_to_app(apps[next].clone());
+        self.set_status(
+            Severity::Info,
+            format!("Switched to {}.", self.current_application),
+        );
+    }
+
+    fn app_keybinds(&self, app_name: &str) -> AppKeybinds {
+        AppKeybinds {
+            application: app_name.to_string(),
+            notes: self.app_notes.get(app_name).cloned().unwrap_or_default(),
+            color: self.app_colors.get(app_name).copied(),
+            keybinds: self
+                .keybinds
+                .iter()
+                .filter(|kb| kb.application == app_name)
+                .map(|kb| KeybindEntry {
+                    keys: kb.keys.clone(),
+                    description: kb.description.clone(),
+                    uses: kb.uses,
+                    kind: kb.kind,
+                    enabled: kb.enabled,
+                    starred: kb.starred,
+                    id: kb.id,
+                })
+                .collect(),
+        }
+    }
+
+    /// Writes the given applications to disk according to the storage mode.
+    fn write_apps(&self, app_names: &[String]) -> Result<(), String> {
+        let dir = get_data_dir();
+        if !dir.exists() {
+            fs::create_dir_all(&dir).map_err(|e| format!("Error creating directory: {}", e))?;
+        }
+
+        match self.config.storage_mode {
+            StorageMode::PerApp => {
+                for app_name in app_names {
+                    if let Some(other) = self.file_name_collision(app_name) {
+                        return Err(format!(
+                            "Error: {} and {} would both be saved as {}; merge or recreate one under another name.",
+                            app_name,
+                            other,
+                            app_file_name(app_name)
+                        ));
+                    }
+                    let path = dir.join(app_file_name(app_name));
+                    let json = to_json(&self.app_keybinds(app_name), &self.config.json_indent)
+                        .map_err(|_| "Error: Failed to serialize keybinds.".to_string())?;
+                    fs::write(&path, json)
+                        .map_err(|_| format!("Error: Failed to write to {}.", path.display()))?;
+                    // Older versions used the name as is, which some systems
+                    // allowed; that file would load as a duplicate.
+                    let old_name = format!("{}.json", app_name);
+                    let old_path = dir.join(&old_name);
+                    if old_path != path
+                        && old_name.to_lowercase() != SINGLE_FILE_NAME
+                        && old_path.parent() == Some(&dir)
+                        && old_path.is_file()
+                    {
+                        fs::remove_file(&old_path)
+                            .map_err(|e| format!("Error deleting {}: {}", old_path.display(), e))?;
+                    }
+                }
+                Ok(())
+            }
+            StorageMode::Single => {
+                let path = dir.join(SINGLE_FILE_NAME);
+                let mut apps = read_single_file(&path)?;
+                for app_name in app_names {
+                    let app_keybinds = self.app_keybinds(app_name);
+                    match apps.iter_mut().find(|a| &a.application == app_name) {
+                        Some(existing) => *existing = app_keybinds,
+                        None => apps.push(app_keybinds),
+                    }
+                }
+                write_single_file(&path, &apps, &self.config.json_indent)
+            }
+        }
+    }
+
+    /// `:saveas`: copies the current application's keybinds, notes and color
+    /// to `name`, saves the copy and switches to it, like vim's `:saveas`.
+    fn save_as(&mut self, name: String) {
+        if name.is_empty() {
+            self.set_status(Severity::Warning, "Usage: :saveas <name>");
+            return;
+        }
+        if self.all_applications.contains(&name) {
+            self.set_status(Severity::Warning, format!("'{}' already exists.", name));
+            return;
+        }
+        self.push_to_undo_history(&format!("Save as {}", name));
+        let source = self.current_application.clone();
+        let copies: Vec<Keybind> = self
+            .keybinds
+            .iter()
+            .filter(|kb| kb.application == source)
+            .cloned()
+            .collect();
+        for mut kb in copies {
+            kb.application = name.clone();
+            kb.id = self.new_keybind_id();
+            self.keybinds.push(kb);
+        }
+        if let Some(notes) = self.app_notes.get(&source).cloned() {
+            self.app_notes.insert(name.clone(), notes);
+        }
+        if let Some(color) = self.app_colors.get(&source).copied() {
+            self.app_colors.insert(name.clone(), color);
+        }
+        self.all_applications.insert(name.clone());
+        // Checked once the name is listed, so a clash with another app's file
+        // is caught too.
+        let result = self.write_apps(std::slice::from_ref(&name));
+        self.switch_to_app(name.clone());
+        match result {
+            Ok(()) => self.set_status(
+                Severity::Success,
+                format!("Saved a copy of {} as {}.", source, name),
+            ),
+            Err(e) => {
+                self.dirty = true;
+                self.set_status(Severity::Error, e);
+            }
+        }
+    }
+
+    /// Another application saved to the same per-app file as `app_name`.
+    /// Names are compared ignoring case, since `Git.json` and `git.json` are
+    /// the same file on macOS and Windows.
+    fn file_name_collision(&self, app_name: &str) -> Option<String> {
+        let file_name = app_file_name(app_name).to_lowercase();
+        self.get_all_applications()
+            .into_iter()
+            .find(|other| other != app_name && app_file_name(other).to_lowercase() == file_name)
+    }
+
+    /// `:w`: saves the current application. Returns whether that succeeded.
+    fn save_current_app_keybinds(&mut self) -> bool {
+        let app_name = self.current_application.clone();
+        match self.write_apps(std::slice::from_ref(&app_name)) {
+            Ok(()) => {
+                self.set_status(
+                    Severity::Success,
+                    format!("Saved {} successfully.", app_name),
+                );
+                for kb in self.keybinds.iter().filter(|kb| kb.application == app_name) {
+                    self.modified_this_session.remove(&kb.id);
+                }
+                // Other applications may still have unsaved changes.
+                self.dirty = self.read_saved_apps().map_or(true, |saved_apps| {
+                    !self.unsaved_apps(&saved_apps).is_empty()
+                });
+                true
+            }
+            Err(e) => {
+                self.set_status(Severity::Error, e);
+                false
+            }
+        }
+    }
+
+    /// `ZZ`: saves the current application, or every one with
+    /// `zz_writes_all`, and quits if that succeeded. Other applications with
+    /// unsaved changes are listed first, like `:q` does.
+    fn save_and_quit(&mut self) {
+        if self.config.zz_writes_all {
+            self.save_all_keybinds();
+            if !self.dirty {
+                self.should_quit = true;
+            }
+        } else if self.save_current_app_keybinds() {
+            self.confirm_quit();
+        }
+    }
+
+    fn save_all_keybinds(&mut self) {
+        let apps = self.get_all_applications();
+        match self
+            .write_apps(&apps)
+            .and_then(|()| self.delete_removed_apps())
+        {
+            Ok(()) => {
+                self.set_status(
+                    Severity::Success,
+                    match self.config.storage_mode {
+                        StorageMode::PerApp => format!("Wrote {} file(s).", apps.len()),
+                        StorageMode::Single => {
+                            format!("Wrote {} app(s) to {}.", apps.len(), SINGLE_FILE_NAME)
+                        }
+                    },
+                );
+                self.modified_this_session.clear();
+                self.dirty = false;
+            }
+            Err(e) => self.set_status(Severity::Error, e),
         }
     }
 
     fn load_all_keybinds(&mut self) {
         self.keybinds.clear();
         self.all_applications.clear();
+        self.app_notes.clear();
+        self.modified_this_session.clear();
+        self.removed_apps.clear();
+        self.app_colors.clear();
         let dir = get_data_dir();
 
         if !dir.exists() {
             if let Err(e) = fs::create_dir_all(&dir) {
-                self.status_message = format!(
-                    "Failed to create data directory at {}: {}",
-                    dir.display(),
-                    e
+                self.set_status(
+                    Severity::Error,
+                    format!(
+                        "Failed to create data directory at {}: {}",
+                        dir.display(),
+                        e
+                    ),
                 );
                 return;
             }
-            self.status_message = format!("Created new data directory at {}.", dir.display());
-        }
-
-        match fs::read_dir(dir) {
-            Ok(entries) => {
-                for entry in entries {
-                    if let Ok(entry) = entry {
-                        let path = entry.path();
-                        if path.is_file()
-                            && path.extension().and_then(|s| s.to_str()) == Some("json")
-                        {
-                            if let Ok(data) = fs::read_to_string(&path) {
-                                if let Ok(app_keybinds) = serde_json::from_str::<AppKeybinds>(&data)
-                                {
-                                    self.all_applications
-                                        .insert(app_keybinds.application.clone());
-                                    for entry in app_keybinds.keybinds {
-                                        self.keybinds.push(Keybind {
-                                            keys: entry.keys,
-                                            description: entry.description,
-                                            application: app_keybinds.application.clone(),
-                                        });
-                                    }
-                                }
-                            }
-                        }
+            self.set_status(
+                Severity::Success,
+                format!("Created new data directory at {}.", dir.display()),
+            );
+        }
+
+        match self.read_saved_apps_with_failures() {
+            Ok((apps, failures)) => {
+                for app_keybinds in apps {
+                    self.all_applications
+                        .insert(app_keybinds.application.clone());
+                    if !app_keybinds.notes.is_empty() {
+                        self.app_notes
+                            .insert(app_keybinds.application.clone(), app_keybinds.notes);
+                    }
+                    if let Some(color) = app_keybinds.color {
+                        self.app_colors
+                            .insert(app_keybinds.application.clone(), color);
+                    }
+                    for entry in app_keybinds.keybinds {
+                        self.keybinds.push(Keybind {
+                            keys: entry.keys,
+                            description: entry.description,
+                            application: app_keybinds.application.clone(),
+                            uses: entry.uses,
+                            kind: entry.kind,
+                            enabled: entry.enabled,
+                            starred: entry.starred,
+                            id: entry.id,
+                        });
                     }
                 }
+                self.assign_missing_ids();
                 if !self.keybinds.is_empty() {
-                    self.status_message = "Keybinds loaded.".to_string();
+                    self.set_status(Severity::Success, "Keybinds loaded.");
                 }
+                if !failures.is_empty() {
+                    let names: Vec<String> = failures
+                        .iter()
+                        .map(|(path, _)| {
+                            path.file_name()
+                                .map(|name| name.to_string_lossy().into_owned())
+                                .unwrap_or_else(|| path.display().to_string())
+                        })
+                        .collect();
+                    self.set_status(
+                        Severity::Warning,
+                        format!(
+                            "{} file(s) failed to load: {}. Run :failed for details.",
+                            failures.len(),
+                            names.join(", ")
+                        ),
+                    );
+                }
+                self.load_failures = failures;
                 self.dirty = false;
             }
-            Err(_) => {
-                self.status_message = "Error reading keybinds directory.".to_string();
+            Err(e) => {
+                self.set_status(Severity::Error, e);
+            }
+        }
+    }
+
+    /// Reads every application as last saved, according to the storage mode.
+    fn read_saved_apps(&self) -> Result<Vec<AppKeybinds>, String> {
+        self.read_saved_apps_with_failures().map(|(apps, _)| apps)
+    }
+
+    /// Like [`Self::read_saved_apps`], but also returns the per-application
+    /// files that were skipped because they couldn't be read or parsed.
+    fn read_saved_apps_with_failures(&self) -> Result<(Vec<AppKeybinds>, LoadFailures), String> {
+        let dir = get_data_dir();
+        match self.config.storage_mode {
+            StorageMode::PerApp => read_per_app_files(&dir)
+                .map(|(files, failed)| (files.into_iter().map(|(_, app)| app).collect(), failed)),
+            StorageMode::Single => {
+                read_single_file(&dir.join(SINGLE_FILE_NAME)).map(|apps| (apps, Vec::new()))
+            }
+        }
+    }
+
+    /// The applications whose keybinds, note or color differ from
+    /// `saved_apps`, plus saved applications that are gone from memory, e.g.
+    /// after `:merge`. An application with nothing in it only counts when it
+    /// has a saved copy.
+    fn unsaved_apps(&self, saved_apps: &[AppKeybinds]) -> Vec<String> {
+        let mut unsaved: Vec<String> = self
+            .get_all_applications()
+            .into_iter()
+            .filter(|name| {
+                let current = self.app_keybinds(name);
+                let is_empty = current.keybinds.is_empty()
+                    && current.notes.is_empty()
+                    && current.color.is_none();
+                let has_saved_copy = saved_apps.iter().any(|app| &app.application == name);
+                (!is_empty || has_saved_copy) && !saved_apps.contains(&current)
+            })
+            .collect();
+        unsaved.extend(
+            saved_apps
+                .iter()
+                .filter(|app| !self.all_applications.contains(&app.application))
+                .map(|app| app.application.clone()),
+        );
+        unsaved
+    }
+
+    /// `:q!`/`ZQ`: quits without saving, first asking for confirmation when
+    /// `confirm_force_quit` is set and there are unsaved changes.
+    fn force_quit(&mut self) {
+        if self.config.confirm_force_quit && self.dirty {
+            self.mode = Mode::ForceQuitConfirm;
+        } else {
+            self.should_quit = true;
+        }
+    }
+
+    /// `:q`: quits straight away when nothing is unsaved, otherwise lists the
+    /// applications with unsaved changes and asks what to do. The saved files
+    /// are always checked, since `dirty` is cleared by saving any one app.
+    fn confirm_quit(&mut self) {
+        match self.read_saved_apps() {
+            Ok(saved_apps) => {
+                self.unsaved_on_quit = self.unsaved_apps(&saved_apps);
+                if self.unsaved_on_quit.is_empty() {
+                    self.should_quit = true;
+                } else {
+                    self.mode = Mode::QuitConfirm;
+                }
+            }
+            Err(e) => self.set_status(
+                Severity::Warning,
+                format!(
+                    "Couldn't check for unsaved changes. Use :q! to force quit. ({})",
+                    e
+                ),
+            ),
+        }
+    }
+
+    /// Replaces the current application's keybinds and note with the saved
+    /// version. Unless `force` is set, refuses when that discards changes.
+    fn revert_current_app(&mut self, force: bool) {
+        let saved_apps = match self.read_saved_apps() {
+            Ok(apps) => apps,
+            Err(e) => {
+                self.set_status(Severity::Error, e);
+                return;
+            }
+        };
+        let app_name = self.current_application.clone();
+        let saved = saved_apps
+            .iter()
+            .find(|app| app.application == app_name)
+            .cloned()
+            .unwrap_or_else(|| AppKeybinds {
+                application: app_name.clone(),
+                notes: String::new(),
+                color: None,
+                keybinds: Vec::new(),
+            });
+
+        if self.app_keybinds(&app_name) == saved {
+            self.set_status(
+                Severity::Info,
+                format!("No changes to {} since the last save.", app_name),
+            );
+            return;
+        }
+        if !force {
+            self.set_status(
+                Severity::Warning,
+                format!(
+                    "Unsaved changes to {} will be lost. Use :revert! to discard them.",
+                    app_name
+                ),
+            );
+            return;
+        }
+
+        self.push_to_undo_history(&format!("Revert {}", app_name));
+        for kb in self.keybinds.iter().filter(|kb| kb.application == app_name) {
+            self.modified_this_session.remove(&kb.id);
+        }
+        self.keybinds.retain(|kb| kb.application != app_name);
+        for entry in saved.keybinds {
+            self.keybinds.push(Keybind {
+                keys: entry.keys,
+                description: entry.description,
+                application: app_name.clone(),
+                uses: entry.uses,
+                kind: entry.kind,
+                enabled: entry.enabled,
+                starred: entry.starred,
+                id: entry.id,
+            });
+        }
+        self.assign_missing_ids();
+        if saved.notes.is_empty() {
+            self.app_notes.remove(&app_name);
+        } else {
+            self.app_notes.insert(app_name.clone(), saved.notes);
+        }
+        match saved.color {
+            Some(color) => self.app_colors.insert(app_name.clone(), color),
+            None => self.app_colors.remove(&app_name),
+        };
+
+        // Still dirty if any other application differs from what's saved.
+        self.dirty = !self.unsaved_apps(&saved_apps).is_empty();
+        self.refilter();
+        self.clamp_selection();
+        self.set_status(
+            Severity::Success,
+            format!("Reverted {} to the last save.", app_name),
+        );
+    }
+
+    /// Merges every per-application file in the data directory into the
+    /// combined `keybinds.json`. The per-application files are left in place.
+    fn consolidate(&mut self) {
+        let dir = get_data_dir();
+        let (per_app, failed) = match read_per_app_files(&dir) {
+            Ok(files) => files,
+            Err(e) => {
+                self.set_status(Severity::Error, e);
+                return;
+            }
+        };
+        if per_app.is_empty() {
+            self.set_status(Severity::Warning, "No per-app files to consolidate.");
+            return;
+        }
+
+        let path = dir.join(SINGLE_FILE_NAME);
+        let mut combined = match read_single_file(&path) {
+            Ok(apps) => apps,
+            Err(e) => {
+                self.set_status(Severity::Error, e);
+                return;
+            }
+        };
+
+        let count = per_app.len();
+        for (_, app) in per_app {
+            match combined
+                .iter_mut()
+                .find(|a| a.application == app.application)
+            {
+                Some(existing) => {
+                    for entry in app.keybinds {
+                        if !existing.keybinds.contains(&entry) {
+                            existing.keybinds.push(entry);
+                        }
+                    }
+                }
+                None => combined.push(app),
+            }
+        }
+
+        match write_single_file(&path, &combined, &self.config.json_indent) {
+            Ok(()) => {
+                self.set_status(
+                    Severity::Success,
+                    if !failed.is_empty() {
+                        format!(
+                            "Consolidated {} app(s) into {}, skipping {} unreadable file(s).",
+                            count,
+                            SINGLE_FILE_NAME,
+                            failed.len()
+                        )
+                    } else if self.config.storage_mode == StorageMode::Single {
+                        format!("Consolidated {} app(s) into {}.", count, SINGLE_FILE_NAME)
+                    } else {
+                        format!(
+                        "Consolidated {} app(s) into {}. Set storage_mode = \"single\" to use it.",
+                        count, SINGLE_FILE_NAME
+                    )
+                    },
+                );
             }
+            Err(e) => self.set_status(Severity::Error, e),
         }
     }
 
     fn refilter(&mut self) {
+        self.last_scrolled_row = None;
         let matcher = SkimMatcherV2::default();
-        let search_query: String = self
-            .search_query
-            .chars()
-            .filter(|c| !c.is_whitespace())
-            .collect::<String>()
-            .to_lowercase();
+        let (mut search_field, search_terms) = parse_search_query(&self.search_query);
+        // Other applications stay hidden while showing a single app.
+        if self.single_app && search_field == SearchField::App {
+            search_field = SearchField::Any;
+        }
+        // A prefix overrides the scope for a single search.
+        if search_field == SearchField::Any {
+            search_field = self.search_scope.field();
+        }
         let current_app = &self.current_application;
+        let modifier_filter = self.modifier_filter;
+        let starred_view = self.starred_view;
+        // The row being filled in after `o`/`O`/`:add` stays visible until
+        // it's been edited.
+        let hide_empty = self.hide_empty;
+        let new_keybind_index = self.new_keybind_index;
+        let notation = self.config.keybind_notation;
+        let key_display = self.config.key_display;
+        let delimiter = &self.config.chord_delimiter;
 
-        self.filtered_items = self
+        // Each row is paired with its match score for `sort_by_relevance`.
+        let mut scored_items: Vec<(i64, FilteredItem)> = self
             .keybinds
             .iter()
             .enumerate()
             .filter_map(|(idx, kb)| {
-                if &kb.application != current_app {
+                if new_keybind_index == Some(idx) {
+                    return Some((
+                        0,
+                        FilteredItem {
+                            original_index: idx,
+                            match_indices: None,
+                        },
+                    ));
+                }
+                // `app:` searches every application by name.
+                if search_field == SearchField::App {
+                    return fuzzy_match_all(&matcher, &kb.application, &search_terms).map(
+                        |(score, _)| {
+                            (
+                                score,
+                                FilteredItem {
+                                    original_index: idx,
+                                    match_indices: None,
+                                },
+                            )
+                        },
+                    );
+                }
+                if starred_view {
+                    if !kb.starred {
+                        return None;
+                    }
+                } else if &kb.application != current_app {
+                    return None;
+                }
+                // Headers only structure the full list.
+                if kb.kind == RowKind::Header
+                    && (modifier_filter.is_some() || !search_terms.is_empty())
+                {
+                    return None;
+                }
+                if modifier_filter.is_some_and(|filter| !filter.matches(&kb.keys)) {
+                    return None;
+                }
+                if hide_empty && kb.keys.trim().is_empty() && kb.description.trim().is_empty() {
                     return None;
                 }
-                if search_query.is_empty() {
-                    Some(FilteredItem {
-                        original_index: idx,
-                        match_indices: None,
-                    })
+                if search_terms.is_empty() {
+                    Some((
+                        0,
+                        FilteredItem {
+                            original_index: idx,
+                            match_indices: None,
+                        },
+                    ))
                 } else {
-                    let combined_string = format!("{} {}", kb.keys, kb.description);
-                    if let Some((_, indices)) =
-                        matcher.fuzzy_indices(&combined_string, &search_query)
-                    {
-                        Some(FilteredItem {
+                    // Indices are relative to "<keys> <description>", which is
+                    // how `draw_table_row` highlights them.
+                    let keys = display_keys(&kb.keys, notation, key_display, delimiter);
+                    let (score, indices) = match search_field {
+                        SearchField::Keys => fuzzy_match_all(&matcher, &keys, &search_terms),
+                        SearchField::Description => {
+                            fuzzy_match_all(&matcher, &kb.description, &search_terms).map(
+                                |(score, indices)| {
+                                    let offset = keys.chars().count() + 1;
+                                    (score, indices.into_iter().map(|i| i + offset).collect())
+                                },
+                            )
+                        }
+                        _ => fuzzy_match_all(
+                            &matcher,
+                            &format!("{} {}", keys, kb.description),
+                            &search_terms,
+                        ),
+                    }?;
+                    Some((
+                        score,
+                        FilteredItem {
                             original_index: idx,
                             match_indices: Some(indices),
-                        })
-                    } else {
-                        None
-                    }
+                        },
+                    ))
                 }
             })
             .collect();
+        // The row being filled in keeps its place so the selection stays on it.
+        if self.config.sort_by_relevance && !search_terms.is_empty() && new_keybind_index.is_none()
+        {
+            scored_items.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
+        }
+        self.filtered_items = scored_items.into_iter().map(|(_, item)| item).collect();
         self.clamp_selection();
     }
 
+    /// Refilters for a changed search, keeping the selection on the same
+    /// keybind if it's still listed and moving it to the first result if not.
+    fn refilter_keeping_selection(&mut self) {
+        let selected_id = self
+            .filtered_items
+            .get(self.selected_cell.0)
+            .map(|item| self.keybinds[item.original_index].id);
+        self.refilter();
+        self.selected_cell.0 = selected_id
+            .and_then(|id| {
+                self.filtered_items
+                    .iter()
+                    .position(|item| self.keybinds[item.original_index].id == id)
+            })
+            .unwrap_or(0);
+    }
+
     fn clamp_selection(&mut self) {
         let num_rows = self.filtered_items.len();
         if num_rows == 0 {
@@ -422,13 +2427,27 @@ impl AppState {
         self.selected_cell.1 = self.selected_cell.1.min(1);
     }
 
+    /// Moves the selected row by `delta`, clamped to the filtered list.
+    fn move_selection_by(&mut self, delta: isize) {
+        let last_row = self.filtered_items.len().saturating_sub(1);
+        self.selected_cell.0 = self
+            .selected_cell
+            .0
+            .saturating_add_signed(delta)
+            .min(last_row);
+    }
+
     fn enter_insert_mode(&mut self) {
-        if self.filtered_items.is_empty() && !self.just_created_new_keybind {
+        let Some(item) = self.filtered_items.get(self.selected_cell.0) else {
             return;
-        }
+        };
+        let real_idx = item.original_index;
         self.mode = Mode::Insert;
-        let (row_idx, col_idx) = self.selected_cell;
-        let real_idx = self.filtered_items[row_idx].original_index;
+        // A header only has its text, kept in the description.
+        if self.keybinds[real_idx].kind == RowKind::Header {
+            self.selected_cell.1 = 1;
+        }
+        let (_, col_idx) = self.selected_cell;
 
         self.temp_edit_buffer = match col_idx {
             0 => {
@@ -441,13 +2460,209 @@ impl AppState {
         };
     }
 
-    fn exit_insert_mode(&mut self, saved: bool) {
-        self.is_listening_for_keybind = false;
-        let (row_idx, col_idx) = self.selected_cell;
+    /// Inserts a section header above the selected row, or at the end of the
+    /// current application when nothing is selected.
+    fn insert_header(&mut self, text: String) {
+        if text.is_empty() {
+            self.set_status(Severity::Warning, "Usage: :header <text>");
+            return;
+        }
+        let insert_pos = match self.filtered_items.get(self.selected_cell.0) {
+            Some(item) => item.original_index,
+            None => self
+                .keybinds
+                .iter()
+                .rposition(|kb| kb.application == self.current_application)
+                .map_or(self.keybinds.len(), |idx| idx + 1),
+        };
+        self.push_to_undo_history("Add header");
+        let id = self.new_keybind_id();
+        self.keybinds.insert(
+            insert_pos,
+            Keybind {
+                keys: String::new(),
+                description: text,
+                application: self.current_application.clone(),
+                uses: 0,
+                kind: RowKind::Header,
+                enabled: true,
+                starred: false,
+                id,
+            },
+        );
+        self.modified_this_session.insert(id);
+        self.refilter();
+        if let Some(row) = self
+            .filtered_items
+            .iter()
+            .position(|item| item.original_index == insert_pos)
+        {
+            self.selected_cell.0 = row;
+        }
+        self.set_status(Severity::Success, "Header added.");
+    }
 
-        if saved {
+    /// Lists every keybind, in any application, whose keys or description
+    /// contain `pattern`, ignoring case.
+    fn grep(&mut self, pattern: &str) {
+        let needle = pattern.to_lowercase();
+        self.grep_results = self
+            .keybinds
+            .iter()
+            .enumerate()
+            .filter(|(_, kb)| kb.kind == RowKind::Keybind)
+            .filter(|(_, kb)| !self.single_app || kb.application == self.current_application)
+            .filter(|(_, kb)| {
+                kb.keys.to_lowercase().contains(&needle)
+                    || kb.description.to_lowercase().contains(&needle)
+            })
+            .map(|(idx, _)| idx)
+            .collect();
+        // Group the results by application, keeping table order within each.
+        let keybinds = &self.keybinds;
+        self.grep_results
+            .sort_by(|&a, &b| keybinds[a].application.cmp(&keybinds[b].application));
+        self.grep_pattern = pattern.to_string();
+        self.grep_selected_index = 0;
+        if self.grep_results.is_empty() {
+            self.set_status(Severity::Info, format!("No matches for '{}'.", pattern));
+        } else {
+            self.mode = Mode::Grep;
+        }
+    }
+
+    /// Switches to the application of `keybinds[index]` and selects it,
+    /// clearing filters that would hide it.
+    fn select_keybind(&mut self, index: usize) {
+        let app_name = self.keybinds[index].application.clone();
+        self.switch_to_app(app_name);
+        self.search_query.clear();
+        self.modifier_filter = None;
+        self.starred_view = false;
+        self.refilter();
+        if let Some(row) = self
+            .filtered_items
+            .iter()
+            .position(|item| item.original_index == index)
+        {
+            self.selected_cell = (row, 0);
+        }
+    }
+
+    /// Opens the export menu, from `<Space>e` or `:export`.
+    fn open_export_menu(&mut self) {
+        self.mode = Mode::Export;
+    }
+
+    /// Opens the import menu, from `<Space>i` or `:import`.
+    fn open_import_menu(&mut self) {
+        if !self.is_locked() {
+            self.mode = Mode::Import;
+        }
+    }
+
+    /// `o`/`O`: adds an empty keybind below the selected row, or above it when
+    /// `above` is set, and starts editing its keys. With nothing selected the
+    /// row goes first.
+    fn insert_new_keybind(&mut self, above: bool) {
+        let insert_pos = match self.filtered_items.get(self.selected_cell.0) {
+            Some(item) if above => item.original_index,
+            Some(item) => item.original_index + 1,
+            None => 0,
+        };
+        let id = self.new_keybind_id();
+        self.keybinds.insert(
+            insert_pos,
+            Keybind {
+                keys: "".into(),
+                description: "".into(),
+                application: self.current_application.clone(),
+                uses: 0,
+                kind: RowKind::Keybind,
+                enabled: true,
+                starred: false,
+                id,
+            },
+        );
+        self.modified_this_session.insert(id);
+        self.new_keybind_index = Some(insert_pos);
+        self.refilter();
+        // Look the row up rather than assuming where it lands, which differs
+        // for an empty list and for rows sorted by relevance.
+        if let Some(row) = self
+            .filtered_items
+            .iter()
+            .position(|item| item.original_index == insert_pos)
+        {
+            self.selected_cell = (row, 0);
+        }
+        self.just_created_new_keybind = true;
+        self.enter_insert_mode();
+    }
+
+    /// Adds an empty keybind after the current application's last one,
+    /// whatever the selection, and starts editing its keys.
+    fn append_new_keybind(&mut self) {
+        let insert_pos = self
+            .keybinds
+            .iter()
+            .rposition(|kb| kb.application == self.current_application)
+            .map_or(self.keybinds.len(), |idx| idx + 1);
+        let id = self.new_keybind_id();
+        self.keybinds.insert(
+            insert_pos,
+            Keybind {
+                keys: "".into(),
+                description: "".into(),
+                application: self.current_application.clone(),
+                uses: 0,
+                kind: RowKind::Keybind,
+                enabled: true,
+                starred: false,
+                id,
+            },
+        );
+        self.modified_this_session.insert(id);
+        self.new_keybind_index = Some(insert_pos);
+        self.refilter();
+        if let Some(row) = self
+            .filtered_items
+            .iter()
+            .position(|item| item.original_index == insert_pos)
+        {
+            self.selected_cell = (row, 0);
+        }
+        self.just_created_new_keybind = true;
+        self.enter_insert_mode();
+    }
+
+    /// Whether the cell being edited is a description that takes newlines.
+    fn editing_multiline(&self) -> bool {
+        self.config.multiline_descriptions
+            && self.selected_cell.1 == 1
+            && self
+                .filtered_items
+                .get(self.selected_cell.0)
+                .is_some_and(|item| self.keybinds[item.original_index].kind == RowKind::Keybind)
+    }
+
+    /// Edits the selected row's keys as text instead of capturing them.
+    fn enter_keys_text_mode(&mut self) {
+        self.selected_cell.1 = 0;
+        self.enter_insert_mode();
+        self.is_listening_for_keybind = false;
+        self.ignore_next_input_frame = false;
+    }
+
+    fn exit_insert_mode(&mut self, saved: bool) {
+        self.is_listening_for_keybind = false;
+        self.captured_keys = None;
+        self.capturing_sequence = false;
+        let (row_idx, col_idx) = self.selected_cell;
+
+        if saved {
             if self.just_created_new_keybind && col_idx == 0 {
-                self.push_to_undo_history();
+                self.push_to_undo_history("Add keybind");
             }
 
             if let Some(item) = self.filtered_items.get(row_idx) {
@@ -457,14 +2672,21 @@ impl AppState {
                     1 => &kb.description,
                     _ => "",
                 };
-                if old_val != &self.temp_edit_buffer {
+                if old_val != self.temp_edit_buffer {
                     self.dirty = true;
+                    self.modified_this_session.insert(kb.id);
                 }
                 match col_idx {
                     0 => kb.keys = self.temp_edit_buffer.clone(),
                     1 => kb.description = self.temp_edit_buffer.clone(),
                     _ => {}
                 }
+                // Saved anyway, since odd keys may be documented on purpose.
+                if col_idx == 0 && kb.kind == RowKind::Keybind {
+                    if let Err(e) = validate_keybind(&kb.keys, &self.config.chord_delimiter) {
+                        self.set_status(Severity::Warning, format!("Check the keys: {}.", e));
+                    }
+                }
             }
 
             if self.just_created_new_keybind && col_idx == 0 {
@@ -485,693 +2707,4301 @@ impl AppState {
         self.mode = Mode::Normal;
         self.temp_edit_buffer.clear();
         self.just_created_new_keybind = false;
+        if self.new_keybind_index.take().is_some() {
+            self.refilter();
+        }
     }
 
-    fn push_to_undo_history(&mut self) {
-        if self.undo_history.len() >= MAX_UNDO_HISTORY {
-            self.undo_history.remove(0);
+    /// Reports keymap entries that don't name a known key. Those bindings fall
+    /// back to their defaults at runtime.
+    fn report_unknown_keys(&mut self) {
+        let unknown: Vec<String> = self
+            .config
+            .keymap
+            .unknown_keys()
+            .into_iter()
+            .map(|(field, value)| format!("'{}' key '{}' unknown", field, value))
+            .collect();
+        if !unknown.is_empty() {
+            self.set_status(
+                Severity::Warning,
+                format!("config: {}, using default", unknown.join(", ")),
+            );
         }
-        self.undo_history.push(self.keybinds.clone());
-        self.dirty = true;
     }
 
-    fn undo(&mut self) {
-        if let Some(last_state) = self.undo_history.pop() {
-            self.keybinds = last_state;
-            self.refilter();
-            self.dirty = true;
-            self.status_message = "Undo successful.".to_string();
-        } else {
-            self.status_message = "Nothing to undo.".to_string();
+    /// Re-reads config.toml, keeping the current settings if it fails to parse.
+    fn reload_config(&mut self) {
+        match read_config(&get_config_dir().join("config.toml")) {
+            Ok(config) => {
+                self.config = config;
+                self.refilter();
+                self.set_status(Severity::Success, "Config reloaded.");
+                self.report_unknown_keys();
+            }
+            Err(e) => self.set_status(Severity::Error, format!("Error: {}", e)),
         }
     }
-}
-
-fn main() -> Result<(), eframe::Error> {
-    let args: Vec<String> = std::env::args().collect();
-    let debug_mode = args.contains(&"--debug".to_string());
 
-    let options = eframe::NativeOptions {
-        viewport: egui::ViewportBuilder::default().with_inner_size([800.0, 600.0]),
-        ..Default::default()
-    };
-    eframe::run_native(
-        "ctrlset",
-        options,
-        Box::new(move |_cc| Box::new(App::new(debug_mode))),
-    )
-}
+    /// `:set <option> [value]`: shows an option, or changes it for the session
+    /// and with `persist` in `config.toml` too.
+    fn set_option(&mut self, key: &str, raw: &str, persist: bool) {
+        if raw.is_empty() {
+            match toml::Table::try_from(&self.config)
+                .ok()
+                .and_then(|mut table| table.remove(key))
+            {
+                Some(value) => self.set_status(Severity::Info, format!("{} = {}", key, value)),
+                None => self.set_status(Severity::Warning, format!("Unknown option: {}", key)),
+            }
+            return;
+        }
+        if key == "storage_mode" {
+            // The loaded data would no longer match the files it came from.
+            self.set_status(
+                Severity::Warning,
+                "storage_mode can't change while running. Use :consolidate, or edit config.toml and restart.",
+            );
+            return;
+        }
+        let (config, value) = match self.config.with_value(key, raw) {
+            Ok(result) => result,
+            Err(e) => {
+                self.set_status(Severity::Error, e);
+                return;
+            }
+        };
+        self.config = config;
+        // These only seed the session's state on startup.
+        match key {
+            "hide_empty" => self.hide_empty = self.config.hide_empty,
+            "search_scope" => self.search_scope = self.config.search_scope,
+            _ => {}
+        }
+        self.refilter();
+        if !persist {
+            self.set_status(Severity::Info, format!("{} = {}", key, value));
+            return;
+        }
+        let path = get_config_dir().join("config.toml");
+        match write_config_value(&path, key, value.clone()) {
+            Ok(()) => self.set_status(
+                Severity::Success,
+                format!("{} = {} (saved to config.toml)", key, value),
+            ),
+            Err(e) => self.set_status(Severity::Error, format!("Error: {}", e)),
+        }
+    }
 
-struct App {
-    state: AppState,
-}
-impl App {
-    fn new(debug_mode: bool) -> Self {
-        Self {
-            state: AppState::new(debug_mode),
+    /// Changes the UI zoom and saves it to `config.toml`.
+    fn set_zoom(&mut self, zoom: f32) {
+        let zoom = (zoom.clamp(MIN_ZOOM, MAX_ZOOM) * 100.0).round() / 100.0;
+        self.config.zoom = zoom;
+        let path = get_config_dir().join("config.toml");
+        // Round in f64 too, so the file doesn't end up with 1.100000023841858.
+        let value = toml::Value::Float((f64::from(zoom) * 100.0).round() / 100.0);
+        match write_config_value(&path, "zoom", value) {
+            Ok(()) => self.set_status(Severity::Info, format!("Zoom {:.0}%.", zoom * 100.0)),
+            Err(e) => self.set_status(Severity::Error, format!("Error: {}", e)),
         }
     }
-}
 
-impl eframe::App for App {
-    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
-        let state = &mut self.state;
+    fn set_status(&mut self, severity: Severity, message: impl Into<String>) {
+        self.status_message = message.into();
+        self.status_severity = severity;
+        self.status_set_at = Instant::now();
+    }
 
-        let title = if state.dirty {
-            "ctrlset [*]"
-        } else {
-            "ctrlset"
-        };
-        ctx.send_viewport_cmd(egui::ViewportCommand::Title(title.to_string()));
+    /// Adds the status message to `message_history` if it changed since it
+    /// was last logged, and clears info and success messages once
+    /// `status_timeout_secs` has passed.
+    fn log_status_message(&mut self) {
+        let timeout = self.config.status_timeout_secs;
+        if timeout > 0.0
+            && self.status_severity <= Severity::Success
+            && self.status_set_at.elapsed().as_secs_f32() >= timeout
+        {
+            self.status_message.clear();
+        }
 
-        if state.is_listening_for_keybind {
-            handle_key_capture(ctx, state);
-        } else {
-            handle_global_input(ctx, state);
+        if self.status_message.is_empty()
+            || self.message_history.back().map(|(_, _, m)| m) == Some(&self.status_message)
+        {
+            return;
+        }
+        if self.message_history.len() >= MAX_MESSAGE_HISTORY {
+            self.message_history.pop_front();
         }
+        self.message_history.push_back((
+            Instant::now(),
+            self.status_severity,
+            self.status_message.clone(),
+        ));
+    }
 
-        if state.should_quit {
-            ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+    /// Returns `true` and reports it when edits are blocked by `:lock`.
+    fn is_locked(&mut self) -> bool {
+        if self.locked {
+            self.set_status(Severity::Warning, "Locked. Use :unlock to edit.");
         }
+        self.locked
+    }
 
-        egui::CentralPanel::default().show(ctx, |ui| {
-            ui.with_layout(Layout::top_down(egui::Align::Center), |ui| {
-                ui.add_space(20.0);
-                Frame::group(ui.style()).show(ui, |ui| {
-                    draw_main_table(ui, state);
-                });
-            });
-        });
+    /// Returns `true` and reports it when app switching is disabled.
+    fn is_single_app(&mut self) -> bool {
+        if self.single_app {
+            self.set_status(
+                Severity::Warning,
+                "Showing a single app. Use :all-apps to switch apps.",
+            );
+        }
+        self.single_app
+    }
 
-        egui::TopBottomPanel::bottom("status_bar").show(ctx, |ui| {
-            draw_status_bar(ui, state);
+    fn push_to_undo_history(&mut self, description: &str) {
+        let limit = self.config.undo_history_size;
+        if self.undo_history.len() >= limit {
+            self.undo_history.drain(..=self.undo_history.len() - limit);
+        }
+        self.undo_history.push(UndoEntry {
+            keybinds: self.keybinds.clone(),
+            applications: self.all_applications.clone(),
+            app_notes: self.app_notes.clone(),
+            app_colors: self.app_colors.clone(),
+            current_application: self.current_application.clone(),
+            description: description.to_string(),
+            timestamp: Instant::now(),
         });
+        self.dirty = true;
+    }
 
-        match state.mode {
-            Mode::AppFilter => draw_app_filter_popup(ctx, state),
-            Mode::Export => draw_export_popup(ctx, state),
-            Mode::Import => draw_import_popup(ctx, state),
-            Mode::Help => draw_help_popup(ctx, state),
-            _ => {}
+    fn undo(&mut self) {
+        if let Some(last_state) = self.undo_history.pop() {
+            self.restore_snapshot(last_state);
+            self.dirty = true;
+            self.set_status(Severity::Success, "Undo successful.");
+        } else {
+            self.set_status(Severity::Warning, "Nothing to undo.");
         }
     }
-}
 
-fn handle_key_capture(ctx: &Context, state: &mut AppState) {
-    if state.ignore_next_input_frame {
-        state.ignore_next_input_frame = false;
-        return;
+    /// Puts back the keybinds, applications, notes and colors of `entry`,
+    /// bringing back or removing applications created by `:new` or removed
+    /// by `:merge`.
+    fn restore_snapshot(&mut self, entry: UndoEntry) {
+        for name in self.all_applications.difference(&entry.applications) {
+            self.removed_apps.insert(name.clone());
+        }
+        self.keybinds = entry.keybinds;
+        self.all_applications = entry.applications;
+        self.app_notes = entry.app_notes;
+        self.app_colors = entry.app_colors;
+        self.switch_to_app(entry.current_application);
     }
 
-    ctx.input(|i| {
-        if i.key_pressed(Key::Escape) {
-            state.exit_insert_mode(true);
-            return;
-        }
-        let pressed_key = i.events.iter().find_map(|e| {
-            if let egui::Event::Key {
-                key, pressed: true, ..
-            } = e
-            {
-                Some(*key)
-            } else {
-                None
+    /// Copies the selected keybind to the clipboard as `keys: description`.
+    fn yank_selected_row(&mut self) {
+        match self.filtered_items.get(self.selected_cell.0) {
+            Some(item) => {
+                let kb = &self.keybinds[item.original_index];
+                self.clipboard_text = Some(format!("{}: {}", kb.keys, kb.description));
+                self.set_status(Severity::Success, "Copied to clipboard.");
             }
-        });
+            None => self.set_status(Severity::Warning, "No keybind selected."),
+        }
+    }
 
-        if let Some(key) = pressed_key {
-            if !is_key_just_a_modifier(key) {
-                let mut parts = Vec::new();
-                let mods = i.modifiers;
-                if mods.ctrl {
-                    parts.push("Ctrl".to_string());
-                }
-                if mods.alt {
-                    parts.push("Alt".to_string());
-                }
-                if mods.shift {
-                    parts.push("Shift".to_string());
-                }
-                if mods.mac_cmd {
-                    parts.push("Cmd".to_string());
-                }
-                parts.push(format!("{:?}", key));
-                state.temp_edit_buffer = parts.join("+");
-                state.exit_insert_mode(true);
+    /// Copies the current application's keybinds to the clipboard as JSON.
+    fn yank_app_json(&mut self) {
+        match to_json(
+            &self.app_keybinds(&self.current_application),
+            &self.config.json_indent,
+        ) {
+            Ok(json) => {
+                self.clipboard_text = Some(json);
+                self.set_status(Severity::Success, "Copied to clipboard.");
             }
+            Err(_) => self.set_status(Severity::Error, "Error: Failed to serialize keybinds."),
         }
-    });
-}
+    }
 
-fn is_key_just_a_modifier(key: Key) -> bool {
-    matches!(
-        key,
-        Key::F1
-            | Key::F2
-            | Key::F3
-            | Key::F4
-            | Key::F5
-            | Key::F6
-            | Key::F7
-            | Key::F8
-            | Key::F9
-            | Key::F10
-            | Key::F11
-            | Key::F12
-            | Key::F13
-            | Key::F14
-            | Key::F15
-            | Key::F16
-            | Key::F17
-            | Key::F18
-            | Key::F19
-            | Key::F20
-    )
-}
+    /// Starts `:paste-json`. The system clipboard is read directly when
+    /// available; otherwise the text arrives with the next Ctrl+V.
+    fn paste_json(&mut self) {
+        #[cfg(feature = "system-clipboard")]
+        if let Some(text) = self.read_system_clipboard() {
+            self.paste_keybinds(&text);
+            return;
+        }
+        if !clipboard_available() {
+            self.set_status(Severity::Error, "No clipboard is available to paste from.");
+            return;
+        }
+        self.awaiting_paste = true;
+        self.set_status(
+            Severity::Info,
+            "Press Ctrl+V to paste keybinds, Escape to cancel.",
+        );
+    }
 
-fn handle_global_input(ctx: &Context, state: &mut AppState) {
-    if state.debug_mode {
-        ctx.input(|i| {
-            i.events.iter().for_each(|e| {
-                if let egui::Event::Key {
-                    key,
-                    pressed: true,
-                    modifiers,
-                    ..
-                } = e
-                {
-                    println!("DEBUG: Key pressed: {:?}, Modifiers: {:?}", key, modifiers);
+    /// Writes `text` to the clipboard, through `arboard` when built with the
+    /// `system-clipboard` feature and through egui otherwise or if that fails.
+    fn write_clipboard(&mut self, ctx: &Context, text: String) {
+        #[cfg(feature = "system-clipboard")]
+        {
+            let result = match self.system_clipboard() {
+                Ok(clipboard) => clipboard.set_text(text.clone()).map_err(|e| e.to_string()),
+                Err(e) => Err(e),
+            };
+            match result {
+                Ok(()) => return,
+                Err(e) if !clipboard_available() => {
+                    self.set_status(
+                        Severity::Error,
+                        format!("Clipboard unavailable, nothing was copied: {}", e),
+                    );
+                    return;
                 }
-            })
-        });
+                Err(_) => {}
+            }
+        }
+        if !clipboard_available() {
+            self.set_status(
+                Severity::Error,
+                "No clipboard is available, nothing was copied.",
+            );
+            return;
+        }
+        ctx.copy_text(text);
     }
 
-    match state.mode {
-        Mode::Normal => handle_normal_mode_input(ctx, state),
-        Mode::Insert => handle_insert_mode_input(ctx, state),
-        Mode::Search => handle_search_mode_input(ctx, state),
-        Mode::Command => handle_command_mode_input(ctx, state),
-        Mode::AppFilter | Mode::Export | Mode::Import | Mode::Help => {}
+    #[cfg(feature = "system-clipboard")]
+    fn system_clipboard(&mut self) -> Result<&mut arboard::Clipboard, String> {
+        match &mut self.system_clipboard {
+            Some(clipboard) => Ok(clipboard),
+            slot @ None => {
+                let clipboard = arboard::Clipboard::new().map_err(|e| e.to_string())?;
+                Ok(slot.insert(clipboard))
+            }
+        }
     }
-}
 
-fn handle_normal_mode_input(ctx: &Context, state: &mut AppState) {
-    ctx.input_mut(|i| {
-        let keymap = state.keymap.clone();
+    #[cfg(feature = "system-clipboard")]
+    fn read_system_clipboard(&mut self) -> Option<String> {
+        self.system_clipboard().ok()?.get_text().ok()
+    }
 
-        let leader_key = string_to_key(&keymap.leader).unwrap_or(Key::Space);
-        let down_key = string_to_key(&keymap.down).unwrap_or(Key::J);
-        let up_key = string_to_key(&keymap.up).unwrap_or(Key::K);
+    /// Imports pasted text, either as `AppKeybinds` JSON or as one
+    /// `keys<TAB>description` pair per line for the current application.
+    fn paste_keybinds(&mut self, text: &str) {
+        if let Ok(app) = serde_json::from_str::<AppKeybinds>(text) {
+            self.import_app_keybinds(app, false);
+            return;
+        }
 
-        if state.leader_key_pressed {
-            let mut consumed = false;
-            if i.consume_key(
-                Modifiers::NONE,
-                string_to_key(&keymap.app_filter).unwrap_or(Key::F),
-            ) {
-                state.mode = Mode::AppFilter;
-                consumed = true;
-            } else if i.consume_key(
-                Modifiers::NONE,
-                string_to_key(&keymap.export_menu).unwrap_or(Key::E),
-            ) {
-                state.mode = Mode::Export;
-                consumed = true;
-            } else if i.consume_key(
-                Modifiers::NONE,
-                string_to_key(&keymap.import_menu).unwrap_or(Key::I),
-            ) {
-                state.mode = Mode::Import;
-                consumed = true;
-            }
-
-            if consumed
-                || i.events
-                    .iter()
-                    .any(|e| matches!(e, egui::Event::Key { pressed: true, .. }))
-            {
-                state.leader_key_pressed = false;
-            }
+        let keybinds: Vec<KeybindEntry> = text
+            .lines()
+            .filter_map(|line| line.split_once('\t'))
+            .map(|(keys, description)| KeybindEntry {
+                keys: keys.trim().to_string(),
+                description: description.trim().to_string(),
+                uses: 0,
+                kind: RowKind::Keybind,
+                enabled: true,
+                starred: false,
+                id: 0,
+            })
+            .collect();
+        if keybinds.is_empty() {
+            self.set_status(
+                Severity::Error,
+                "Error: Clipboard doesn't contain keybinds.",
+            );
             return;
         }
+        self.import_app_keybinds(
+            AppKeybinds {
+                application: self.current_application.clone(),
+                notes: String::new(),
+                color: None,
+                keybinds,
+            },
+            false,
+        );
+    }
 
-        if state.delete_leader_pressed {
-            let mut consumed_key = false;
-            let mut original_indices_to_delete = vec![];
-            let current_row = state.selected_cell.0;
-
-            if i.consume_key(
-                Modifiers::NONE,
-                string_to_key(&keymap.delete_leader).unwrap_or(Key::D),
-            ) {
-                if let Some(item) = state.filtered_items.get(current_row) {
-                    original_indices_to_delete.push(item.original_index);
-                }
-                consumed_key = true;
-            } else if i.consume_key(Modifiers::NONE, down_key) {
-                if let Some(item) = state.filtered_items.get(current_row) {
-                    original_indices_to_delete.push(item.original_index);
-                }
-                if let Some(item) = state.filtered_items.get(current_row + 1) {
-                    original_indices_to_delete.push(item.original_index);
-                }
-                consumed_key = true;
-            } else if i.consume_key(Modifiers::NONE, up_key) {
-                if let Some(item) = state.filtered_items.get(current_row) {
-                    original_indices_to_delete.push(item.original_index);
-                }
-                if current_row > 0 {
-                    if let Some(item) = state.filtered_items.get(current_row - 1) {
-                        original_indices_to_delete.push(item.original_index);
+    /// Merges an `AppKeybinds` file from anywhere on disk into memory without
+    /// copying it into the data directory.
+    fn source_file(&mut self, path: &str) {
+        if path.is_empty() {
+            self.set_status(Severity::Warning, "Usage: :source <path>");
+            return;
+        }
+        let path = expand_home(path);
+        match fs::read_to_string(&path) {
+            Ok(data) => match serde_json::from_str::<AppKeybinds>(&data) {
+                Ok(app) => {
+                    self.import_app_keybinds(app, false);
+                    if self.pending_import.is_none() {
+                        self.set_status(Severity::Success, format!("Sourced {}.", path.display()));
                     }
                 }
-                consumed_key = true;
+                Err(_) => self.set_status(Severity::Error, "Error: Failed to parse JSON file."),
+            },
+            Err(e) => {
+                self.set_status(
+                    Severity::Error,
+                    format!("Error reading {}: {}", path.display(), e),
+                );
             }
+        }
+    }
 
-            if !original_indices_to_delete.is_empty() {
-                state.push_to_undo_history();
-                original_indices_to_delete.sort_unstable();
-                original_indices_to_delete.dedup();
-                original_indices_to_delete.reverse();
+    fn is_undescribed(&self, item: &FilteredItem) -> bool {
+        let kb = &self.keybinds[item.original_index];
+        kb.kind == RowKind::Keybind && kb.description.trim().is_empty()
+    }
 
-                for index in &original_indices_to_delete {
-                    state.keybinds.remove(*index);
-                }
+    fn show_stats(&mut self) {
+        let keybinds = || {
+            self.keybinds.iter().filter(|kb| {
+                kb.application == self.current_application && kb.kind == RowKind::Keybind
+            })
+        };
+        let total = keybinds().count();
+        let undescribed = keybinds()
+            .filter(|kb| kb.description.trim().is_empty())
+            .count();
+        self.set_status(
+            Severity::Info,
+            format!(
+                "{}: {} keybind(s), {} without a description.",
+                self.current_application, total, undescribed
+            ),
+        );
+    }
 
-                state.status_message =
-                    format!("{} keybind(s) deleted.", original_indices_to_delete.len());
-                state.refilter();
-                state.clamp_selection();
+    /// Selects the next row without a description, wrapping around the list.
+    fn select_next_undescribed(&mut self) {
+        let rows = self.filtered_items.len();
+        let next = (1..=rows)
+            .map(|step| (self.selected_cell.0 + step) % rows.max(1))
+            .find(|&row| self.is_undescribed(&self.filtered_items[row]));
+        match next {
+            Some(row) => {
+                self.selected_cell = (row, 1);
+                self.set_status(
+                    Severity::Warning,
+                    format!("Row {} has no description.", row + 1),
+                );
             }
+            None => self.set_status(Severity::Success, "No undescribed keybinds."),
+        }
+    }
 
-            if consumed_key
-                || i.events
-                    .iter()
-                    .any(|e| matches!(e, egui::Event::Key { pressed: true, .. }))
-            {
-                state.delete_leader_pressed = false;
-            }
+    /// Reassigns the selected keybind to `app_name`, creating the application
+    /// if it doesn't exist yet.
+    fn move_selected_to_app(&mut self, app_name: String) {
+        if app_name.is_empty() || app_name == self.current_application {
+            self.set_status(Severity::Warning, "Usage: :move <other app>");
             return;
         }
+        let Some(item) = self.filtered_items.get(self.selected_cell.0) else {
+            self.set_status(Severity::Warning, "No keybind selected.");
+            return;
+        };
+        let index = item.original_index;
+        self.push_to_undo_history(&format!("Move keybind to {}", app_name));
+        self.keybinds[index].application = app_name.clone();
+        self.mark_modified(index);
+        self.all_applications.insert(app_name.clone());
+        self.refilter();
+        self.set_status(
+            Severity::Success,
+            format!("Moved keybind to '{}'.", app_name),
+        );
+    }
 
-        if !state.leader_key_pressed && !state.delete_leader_pressed {
-            if i.consume_key(Modifiers::NONE, leader_key) {
-                state.leader_key_pressed = true;
+    /// Returns the existing applications whose names match `name` when case
+    /// and punctuation are ignored, excluding an exact match.
+    fn near_matching_apps(&self, name: &str) -> Vec<String> {
+        let normalize = |s: &str| {
+            s.chars()
+                .filter(|c| c.is_alphanumeric())
+                .flat_map(char::to_lowercase)
+                .collect::<String>()
+        };
+        let target = normalize(name);
+        self.get_all_applications()
+            .into_iter()
+            .filter(|app| app != name && normalize(app) == target)
+            .collect()
+    }
+
+    /// Imports keybinds, first asking which application to merge into when
+    /// the imported name only differs in case or punctuation from an existing
+    /// one, e.g. `git-cli` and `Git CLI`.
+    fn import_app_keybinds(&mut self, imported_app: AppKeybinds, replace: bool) {
+        if !self.all_applications.contains(&imported_app.application)
+            && !self
+                .near_matching_apps(&imported_app.application)
+                .is_empty()
+        {
+            self.pending_import = Some((imported_app, replace));
+            self.import_match_index = 0;
+            self.mode = Mode::ImportMatch;
+            return;
+        }
+        self.merge_app_keybinds(imported_app, replace);
+    }
+
+    /// Merges `imported_app` into memory, skipping exact duplicates. With
+    /// `replace`, the application's existing keybinds are dropped first.
+    fn merge_app_keybinds(&mut self, imported_app: AppKeybinds, replace: bool) {
+        self.push_to_undo_history(&format!("Import {}", imported_app.application));
+        let warning = malformed_keys_warning(&imported_app.keybinds, &self.config.chord_delimiter);
+        self.add_imported_keybinds(imported_app, replace);
+        self.dirty = true;
+        self.refilter();
+        match warning {
+            Some(warning) => self.set_status(
+                Severity::Warning,
+                format!("Import successful; {}.", warning),
+            ),
+            None => self.set_status(Severity::Success, "Import successful."),
+        }
+    }
+
+    /// Merges every keybinds JSON file in `dir` as one undoable import. Apps
+    /// are merged under their own names without asking about near matches.
+    fn import_folder(&mut self, dir: &Path) {
+        let (files, failed) = match read_per_app_files(dir) {
+            Ok(files) => files,
+            Err(e) => {
+                self.set_status(Severity::Error, e);
                 return;
             }
-            if i.consume_key(
-                Modifiers::NONE,
-                string_to_key(&keymap.delete_leader).unwrap_or(Key::D),
-            ) {
-                state.delete_leader_pressed = true;
+        };
+        let mut apps: Vec<AppKeybinds> = files.into_iter().map(|(_, app)| app).collect();
+        // A backup made in single-file mode is one combined file.
+        match read_single_file(&dir.join(SINGLE_FILE_NAME)) {
+            Ok(combined) => apps.extend(combined),
+            Err(e) => {
+                self.set_status(Severity::Error, e);
                 return;
             }
         }
+        if apps.is_empty() {
+            self.set_status(
+                Severity::Warning,
+                format!("No keybind files found in {}.", dir.display()),
+            );
+            return;
+        }
 
-        if i.consume_key(
-            Modifiers::SHIFT,
-            string_to_key(&keymap.goto_bottom).unwrap_or(Key::G),
-        ) {
-            state.selected_cell.0 = state.filtered_items.len().saturating_sub(1);
+        self.push_to_undo_history(&format!("Import folder {}", dir.display()));
+        let app_count = apps.len();
+        let before = self.keybinds.len();
+        let warning = malformed_keys_warning(
+            apps.iter().flat_map(|app| &app.keybinds),
+            &self.config.chord_delimiter,
+        );
+        for app in apps {
+            self.add_imported_keybinds(app, false);
         }
-        if keymap.goto_top == "G" && i.key_pressed(Key::G) && i.key_down(Key::G) {
-            state.selected_cell.0 = 0;
+        let added = self.keybinds.len() - before;
+        self.dirty = true;
+        self.refilter();
+
+        let summary = format!("Imported {} app(s), {} keybind(s)", app_count, added);
+        if let Some(warning) = warning.filter(|_| failed.is_empty()) {
+            self.set_status(Severity::Warning, format!("{}; {}.", summary, warning));
+        } else if failed.is_empty() {
+            self.set_status(Severity::Success, format!("{}.", summary));
+        } else {
+            self.set_status(
+                Severity::Warning,
+                format!("{}; skipped {} unreadable file(s).", summary, failed.len()),
+            );
         }
+    }
 
-        if i.consume_key(Modifiers::NONE, down_key) {
-            let num_rows = state.filtered_items.len();
-            if num_rows > 0 {
-                state.selected_cell.0 = (state.selected_cell.0 + 1).min(num_rows - 1);
-            }
+    /// Adds an imported application's keybinds, note and color, skipping
+    /// keybinds it already has. With `replace`, its keybinds are dropped first.
+    fn add_imported_keybinds(&mut self, imported_app: AppKeybinds, replace: bool) {
+        self.all_applications
+            .insert(imported_app.application.clone());
+
+        if replace {
+            self.keybinds
+                .retain(|kb| kb.application != imported_app.application);
         }
-        if i.consume_key(Modifiers::NONE, up_key) {
-            state.selected_cell.0 = state.selected_cell.0.saturating_sub(1);
+        if !imported_app.notes.is_empty() {
+            self.app_notes
+                .insert(imported_app.application.clone(), imported_app.notes);
+        }
+        if let Some(color) = imported_app.color {
+            self.app_colors
+                .insert(imported_app.application.clone(), color);
         }
 
-        if keymap
-            .right
+        // Use counts don't make a keybind distinct.
+        let existing_keybinds: HashSet<_> = self
+            .keybinds
             .iter()
-            .any(|k| i.consume_key(Modifiers::NONE, string_to_key(k).unwrap_or(Key::L)))
-        {
-            state.selected_cell.1 = (state.selected_cell.1 + 1).min(1);
+            .filter(|kb| kb.application == imported_app.application)
+            .map(|kb| (kb.keys.clone(), kb.description.clone()))
+            .collect();
+        for entry in imported_app.keybinds {
+            if !existing_keybinds.contains(&(entry.keys.clone(), entry.description.clone())) {
+                let id = self.new_keybind_id();
+                self.keybinds.push(Keybind {
+                    keys: entry.keys,
+                    description: entry.description,
+                    application: imported_app.application.clone(),
+                    uses: entry.uses,
+                    kind: entry.kind,
+                    enabled: entry.enabled,
+                    starred: entry.starred,
+                    id,
+                });
+                self.modified_this_session.insert(id);
+            }
         }
-        if keymap
-            .left
-            .iter()
-            .any(|k| i.consume_key(Modifiers::NONE, string_to_key(k).unwrap_or(Key::H)))
+    }
+
+    /// Trims whitespace in the current application's keybinds and removes
+    /// duplicates with the same keys and description, keeping the first
+    /// occurrence.
+    fn clean_current_app(&mut self) {
+        self.push_to_undo_history("Clean");
+        let mut trimmed = 0;
+        for kb in self
+            .keybinds
+            .iter_mut()
+            .filter(|kb| kb.application == self.current_application)
         {
-            state.selected_cell.1 = state.selected_cell.1.saturating_sub(1);
+            let keys = kb.keys.trim();
+            let description = kb.description.trim();
+            if keys.len() != kb.keys.len() || description.len() != kb.description.len() {
+                kb.keys = keys.to_string();
+                kb.description = description.to_string();
+                self.modified_this_session.insert(kb.id);
+                trimmed += 1;
+            }
         }
 
-        if i.consume_key(
-            Modifiers::NONE,
-            string_to_key(&keymap.insert_mode).unwrap_or(Key::I),
-        ) {
-            if !state.just_created_new_keybind {
-                state.push_to_undo_history();
+        let app_name = self.current_application.clone();
+        let removed = self.remove_duplicates(&app_name);
+
+        self.refilter();
+        self.set_status(
+            Severity::Success,
+            format!(
+                "Trimmed {} keybind(s), removed {} duplicate(s).",
+                trimmed, removed
+            ),
+        );
+    }
+
+    /// Removes rows of `app_name` with the same keys, description and kind as
+    /// an earlier one, adding their use counts to it. Returns how many were
+    /// removed.
+    fn remove_duplicates(&mut self, app_name: &str) -> usize {
+        let mut first_index: HashMap<(String, String, RowKind), usize> = HashMap::new();
+        let mut duplicates = Vec::new();
+        for (idx, kb) in self.keybinds.iter().enumerate() {
+            if kb.application != app_name {
+                continue;
+            }
+            match first_index.entry((kb.keys.clone(), kb.description.clone(), kb.kind)) {
+                Entry::Occupied(first) => duplicates.push((idx, *first.get())),
+                Entry::Vacant(slot) => {
+                    slot.insert(idx);
+                }
             }
-            state.enter_insert_mode();
         }
-        if i.consume_key(
-            Modifiers::NONE,
-            string_to_key(&keymap.search_mode).unwrap_or(Key::Slash),
-        ) {
-            state.mode = Mode::Search;
-            state.search_query.clear();
+        for &(idx, first) in &duplicates {
+            self.keybinds[first].uses += self.keybinds[idx].uses;
         }
-        if i.consume_key(Modifiers::SHIFT, Key::Semicolon)
-            || i.consume_key(
-                Modifiers::NONE,
-                string_to_key(&keymap.command_mode).unwrap_or(Key::Colon),
-            )
-        {
-            state.mode = Mode::Command;
-            state.command_buffer.clear();
+        for &(idx, _) in duplicates.iter().rev() {
+            self.keybinds.remove(idx);
         }
+        duplicates.len()
+    }
 
-        if i.consume_key(
-            Modifiers::NONE,
-            string_to_key(&keymap.new_line_below).unwrap_or(Key::O),
-        ) || i.consume_key(
-            Modifiers::SHIFT,
-            string_to_key(&keymap.new_line_above).unwrap_or(Key::O),
-        ) {
-            let is_shift = i.modifiers.shift;
-            let new_kb = Keybind {
-                keys: "".into(),
-                description: "".into(),
-                application: state.current_application.clone(),
-            };
-            if is_shift {
-                let insert_pos = if state.filtered_items.is_empty() {
-                    0
-                } else {
-                    state.filtered_items[state.selected_cell.0].original_index
-                };
-                state.keybinds.insert(insert_pos, new_kb);
-            } else {
-                let insert_pos = if state.filtered_items.is_empty() {
-                    0
-                } else {
-                    state.filtered_items[state.selected_cell.0].original_index + 1
-                };
-                state
-                    .keybinds
-                    .insert(insert_pos.min(state.keybinds.len()), new_kb);
-                if !state.filtered_items.is_empty() {
-                    state.selected_cell.0 += 1;
-                }
-            }
-            state.refilter();
-            state.selected_cell.1 = 0;
-            state.just_created_new_keybind = true;
-            state.enter_insert_mode();
+    /// Moves every keybind of `source` into `dest` and removes `source`. Only
+    /// memory changes; `:wa` writes `dest` and deletes `source`'s saved copy
+    /// together, so the keybinds can't be lost in between.
+    fn merge_apps(&mut self, source: &str, dest: &str) {
+        if source == dest {
+            self.set_status(Severity::Warning, "Can't merge an application into itself.");
+            return;
         }
-        if i.consume_key(
-            Modifiers::NONE,
-            string_to_key(&keymap.undo).unwrap_or(Key::U),
-        ) {
-            state.undo();
+        for name in [source, dest] {
+            if !self.all_applications.contains(name) {
+                self.set_status(
+                    Severity::Warning,
+                    format!("No application named '{}'.", name),
+                );
+                return;
+            }
         }
-    });
-}
 
-fn handle_insert_mode_input(ctx: &Context, state: &mut AppState) {
-    if state.is_listening_for_keybind {
-        return;
-    }
-    ctx.input_mut(|i| {
-        if i.consume_shortcut(&KeyboardShortcut::new(Modifiers::NONE, Key::Escape))
-            || i.consume_shortcut(&KeyboardShortcut::new(Modifiers::CTRL, Key::OpenBracket))
+        self.push_to_undo_history(&format!("Merge {} into {}", source, dest));
+        let mut moved = 0;
+        for kb in self
+            .keybinds
+            .iter_mut()
+            .filter(|kb| kb.application == source)
         {
-            state.exit_insert_mode(true);
-        } else if i.consume_shortcut(&KeyboardShortcut::new(Modifiers::NONE, Key::Enter)) {
-            state.exit_insert_mode(true);
+            kb.application = dest.to_string();
+            self.modified_this_session.insert(kb.id);
+            moved += 1;
+        }
+        let removed = self.remove_duplicates(dest);
+        if let Some(note) = self.app_notes.remove(source) {
+            self.app_notes.entry(dest.to_string()).or_insert(note);
+        }
+        if let Some(color) = self.app_colors.remove(source) {
+            self.app_colors.entry(dest.to_string()).or_insert(color);
+        }
+        self.all_applications.remove(source);
+        self.removed_apps.insert(source.to_string());
+        if self.current_application == source {
+            self.switch_to_app(dest.to_string());
+        } else {
+            self.refilter();
         }
-    });
-}
 
-fn handle_search_mode_input(ctx: &Context, state: &mut AppState) {
-    ctx.input(|i| {
-        if i.key_pressed(Key::Escape) {
-            state.mode = Mode::Normal;
-            state.search_query.clear();
-            state.refilter();
-        } else if i.key_pressed(Key::Enter) {
-            state.mode = Mode::Normal;
-        } else if i.key_pressed(Key::Backspace) {
-            if state.search_query.is_empty() {
-                state.mode = Mode::Normal;
-            } else {
-                state.search_query.pop();
+        self.set_status(
+            Severity::Success,
+            format!(
+                "Merged {} keybind(s) from {} into {} ({} duplicate(s) removed). :wa to save.",
+                moved, source, dest, removed
+            ),
+        );
+    }
+
+    /// Deletes the saved copy of every application in `removed_apps` that
+    /// hasn't been brought back since.
+    fn delete_removed_apps(&mut self) -> Result<(), String> {
+        let names: Vec<String> = self
+            .removed_apps
+            .iter()
+            .filter(|name| !self.all_applications.contains(*name))
+            .cloned()
+            .collect();
+        let dir = get_data_dir();
+        match self.config.storage_mode {
+            StorageMode::PerApp => {
+                for name in &names {
+                    let file_name = app_file_name(name);
+                    // Another application may be saved under the same name.
+                    if self.all_applications.iter().any(|other| {
+                        app_file_name(other).to_lowercase() == file_name.to_lowercase()
+                    }) {
+                        continue;
+                    }
+                    let path = dir.join(file_name);
+                    if path.exists() {
+                        fs::remove_file(&path)
+                            .map_err(|e| format!("Error deleting {}: {}", path.display(), e))?;
+                    }
+                }
+            }
+            StorageMode::Single => {
+                let path = dir.join(SINGLE_FILE_NAME);
+                if !names.is_empty() && path.exists() {
+                    let mut apps = read_single_file(&path)?;
+                    apps.retain(|app| !names.contains(&app.application));
+                    write_single_file(&path, &apps, &self.config.json_indent)?;
+                }
             }
-            state.refilter();
         }
-    });
+        self.removed_apps.clear();
+        Ok(())
+    }
 
-    let mut query_changed = false;
-    for event in &ctx.input(|i| i.events.clone()) {
-        if let egui::Event::Text(text) = event {
-            state.search_query.push_str(text);
-            query_changed = true;
+    fn new_keybind_id(&mut self) -> u64 {
+        self.next_keybind_id += 1;
+        self.next_keybind_id
+    }
+
+    /// Gives a fresh id to every keybind without one, or whose id is already
+    /// taken by an earlier row, e.g. after a file was copied by hand.
+    fn assign_missing_ids(&mut self) {
+        let max_id = self.keybinds.iter().map(|kb| kb.id).max().unwrap_or(0);
+        self.next_keybind_id = self.next_keybind_id.max(max_id);
+        let mut seen = HashSet::new();
+        for index in 0..self.keybinds.len() {
+            let id = self.keybinds[index].id;
+            if id == 0 || !seen.insert(id) {
+                let id = self.new_keybind_id();
+                self.keybinds[index].id = id;
+                seen.insert(id);
+            }
         }
     }
-    if query_changed {
-        state.refilter();
+
+    /// Records that the keybind at `index` was changed, for the marker next
+    /// to modified rows.
+    fn mark_modified(&mut self, index: usize) {
+        self.modified_this_session.insert(self.keybinds[index].id);
+    }
+
+    /// Marks the selected keybind as disabled, or enables it again.
+    fn toggle_selected_enabled(&mut self) {
+        let Some(item) = self.filtered_items.get(self.selected_cell.0) else {
+            self.set_status(Severity::Warning, "No keybind selected.");
+            return;
+        };
+        let index = item.original_index;
+        if self.keybinds[index].kind == RowKind::Header {
+            self.set_status(Severity::Warning, "Headers can't be disabled.");
+            return;
+        }
+        let enabled = !self.keybinds[index].enabled;
+        self.push_to_undo_history(if enabled {
+            "Enable keybind"
+        } else {
+            "Disable keybind"
+        });
+        self.keybinds[index].enabled = enabled;
+        self.mark_modified(index);
+        self.set_status(
+            Severity::Success,
+            if enabled {
+                "Keybind enabled."
+            } else {
+                "Keybind disabled."
+            },
+        );
+    }
+
+    /// Stars the selected keybind for `:starred`, or unstars it.
+    fn toggle_selected_starred(&mut self) {
+        let Some(item) = self.filtered_items.get(self.selected_cell.0) else {
+            self.set_status(Severity::Warning, "No keybind selected.");
+            return;
+        };
+        let index = item.original_index;
+        if self.keybinds[index].kind == RowKind::Header {
+            self.set_status(Severity::Warning, "Headers can't be starred.");
+            return;
+        }
+        let starred = !self.keybinds[index].starred;
+        self.push_to_undo_history(if starred {
+            "Star keybind"
+        } else {
+            "Unstar keybind"
+        });
+        self.keybinds[index].starred = starred;
+        self.mark_modified(index);
+        self.refilter();
+        self.set_status(
+            Severity::Success,
+            if starred {
+                "Keybind starred."
+            } else {
+                "Keybind unstarred."
+            },
+        );
+    }
+
+    /// `:starred`: shows the starred keybinds of every application in one
+    /// list, or goes back to the current application.
+    fn toggle_starred_view(&mut self) {
+        self.starred_view = !self.starred_view;
+        self.refilter();
+        if !self.starred_view {
+            self.set_status(
+                Severity::Info,
+                format!("Showing {}.", self.current_application),
+            );
+        } else if self.filtered_items.is_empty() {
+            self.set_status(
+                Severity::Info,
+                "No starred keybinds yet. Star one with *; :starred goes back.",
+            );
+        } else {
+            self.set_status(
+                Severity::Info,
+                format!(
+                    "Showing {} starred keybind(s) from all applications.",
+                    self.filtered_items.len()
+                ),
+            );
+        }
+    }
+
+    /// Counts one more use of the selected keybind, for `:sort uses`.
+    fn mark_selected_used(&mut self) {
+        let Some(item) = self.filtered_items.get(self.selected_cell.0) else {
+            self.set_status(Severity::Warning, "No keybind selected.");
+            return;
+        };
+        let index = item.original_index;
+        if self.keybinds[index].kind == RowKind::Header {
+            self.set_status(Severity::Warning, "Headers can't be marked as used.");
+            return;
+        }
+        self.push_to_undo_history("Mark used");
+        self.keybinds[index].uses += 1;
+        self.mark_modified(index);
+        self.set_status(
+            Severity::Success,
+            format!("Marked as used ({}×).", self.keybinds[index].uses),
+        );
+    }
+
+    /// Orders the current application's keybinds by use count, most used
+    /// first. Keybinds used equally often keep their order.
+    fn sort_by_uses(&mut self) {
+        let positions: Vec<usize> = self
+            .keybinds
+            .iter()
+            .enumerate()
+            .filter(|(_, kb)| kb.application == self.current_application)
+            .map(|(idx, _)| idx)
+            .collect();
+        let mut sorted: Vec<Keybind> = positions
+            .iter()
+            .map(|&idx| self.keybinds[idx].clone())
+            .collect();
+        sorted.sort_by_key(|kb| std::cmp::Reverse(kb.uses));
+        if sorted
+            .iter()
+            .zip(&positions)
+            .all(|(kb, &idx)| *kb == self.keybinds[idx])
+        {
+            self.set_status(Severity::Info, "Already sorted by uses.");
+            return;
+        }
+
+        self.push_to_undo_history("Sort by uses");
+        for (kb, idx) in sorted.into_iter().zip(positions) {
+            self.keybinds[idx] = kb;
+        }
+        self.refilter();
+        self.set_status(Severity::Success, "Sorted by uses.");
+    }
+
+    /// Shows `dir` in the status bar, copies it to the clipboard and opens it
+    /// in the file manager.
+    fn open_dir(&mut self, dir: &Path) {
+        self.clipboard_text = Some(dir.display().to_string());
+        self.set_status(
+            Severity::Info,
+            match open_with_system(dir) {
+                Ok(()) => format!("Opened {} (path copied).", dir.display()),
+                Err(_) => format!("{} (path copied).", dir.display()),
+            },
+        );
+    }
+
+    /// `:config`: opens config.toml in the default editor for `.toml` files,
+    /// writing the current settings first if it's gone missing.
+    fn open_config_file(&mut self) {
+        let path = get_config_dir().join("config.toml");
+        if !path.exists() {
+            let written = toml::to_string_pretty(&self.config)
+                .map_err(|e| e.to_string())
+                .and_then(|toml_string| fs::write(&path, toml_string).map_err(|e| e.to_string()));
+            if let Err(e) = written {
+                self.set_status(
+                    Severity::Error,
+                    format!("Error: Failed to write {}: {}", path.display(), e),
+                );
+                return;
+            }
+        }
+        self.clipboard_text = Some(path.display().to_string());
+        match open_with_system(&path) {
+            Ok(()) => self.set_status(
+                Severity::Info,
+                format!(
+                    "Opened {} (path copied). Run :reload after saving it.",
+                    path.display()
+                ),
+            ),
+            Err(e) => self.set_status(
+                Severity::Error,
+                format!("Couldn't open {} (path copied): {}", path.display(), e),
+            ),
+        }
+    }
+
+    /// Sets the selected row's keys as typed. This covers keys that capture
+    /// can't tell apart, such as numpad digits or right-hand modifiers, which
+    /// egui reports the same as their main keyboard counterparts.
+    fn set_selected_keys(&mut self, keys: String) {
+        if keys.is_empty() {
+            self.set_status(Severity::Warning, "Usage: :keys <text>");
+            return;
+        }
+        let Some(item) = self.filtered_items.get(self.selected_cell.0) else {
+            self.set_status(Severity::Warning, "No keybind selected.");
+            return;
+        };
+        let index = item.original_index;
+        self.push_to_undo_history("Edit keybind");
+        self.keybinds[index].keys = keys;
+        self.mark_modified(index);
+        self.refilter();
+        self.set_status(Severity::Success, "Keys updated.");
+    }
+
+    /// Sets the note shown above the current application's table. An empty
+    /// note removes it.
+    fn set_note(&mut self, note: String) {
+        self.push_to_undo_history("Set note");
+        if note.is_empty() {
+            self.app_notes.remove(&self.current_application);
+            self.set_status(
+                Severity::Success,
+                format!("Cleared note for {}.", self.current_application),
+            );
+        } else {
+            self.app_notes
+                .insert(self.current_application.clone(), note);
+            self.set_status(
+                Severity::Success,
+                format!("Set note for {}.", self.current_application),
+            );
+        }
+    }
+
+    /// Sets the accent color of the current application's name from
+    /// `#rrggbb`, or clears it when `color` is empty.
+    fn set_color(&mut self, color: &str) {
+        if color.is_empty() {
+            self.push_to_undo_history("Clear color");
+            self.app_colors.remove(&self.current_application);
+            self.set_status(
+                Severity::Success,
+                format!("Cleared color for {}.", self.current_application),
+            );
+        } else {
+            let Some(rgb) = parse_hex_color(color) else {
+                self.set_status(Severity::Warning, "Usage: :color #rrggbb");
+                return;
+            };
+            self.push_to_undo_history("Set color");
+            self.app_colors
+                .insert(self.current_application.clone(), rgb);
+            self.set_status(
+                Severity::Success,
+                format!("Set color for {}.", self.current_application),
+            );
+        }
+    }
+
+    /// The color `app_name` is shown in, its `:color` or the default accent.
+    fn app_color(&self, app_name: &str) -> Color32 {
+        match self.app_colors.get(app_name) {
+            Some(&[r, g, b]) => Color32::from_rgb(r, g, b),
+            None => Color32::LIGHT_BLUE,
+        }
+    }
+
+    /// Restores the snapshot at `index` in `undo_history`. The current state is
+    /// pushed first so the jump itself can be undone.
+    fn jump_to_history(&mut self, index: usize) {
+        let Some(entry) = self.undo_history.get(index) else {
+            return;
+        };
+        let snapshot = entry.clone();
+        let description = entry.description.clone();
+        self.push_to_undo_history(&format!("Jump to '{}'", description));
+        self.restore_snapshot(snapshot);
+        self.set_status(
+            Severity::Success,
+            format!("Restored state before '{}'.", description),
+        );
+    }
+    /// Executes a command-mode command (without the leading `:`). Commands that
+    /// open a popup switch to its mode; everything else returns to Normal mode.
+    fn run_command(&mut self, command: &str) {
+        let parts: Vec<&str> = command.split_whitespace().collect();
+        self.mode = Mode::Normal;
+        match parts.as_slice() {
+            ["w"] => {
+                self.save_current_app_keybinds();
+            }
+            ["wq"] => {
+                if self.save_current_app_keybinds() {
+                    self.confirm_quit();
+                }
+            }
+            ["wa"] => self.save_all_keybinds(),
+            ["wqa"] => {
+                self.save_all_keybinds();
+                if !self.dirty {
+                    self.should_quit = true;
+                }
+            }
+            ["q"] => self.confirm_quit(),
+            ["q!"] => self.force_quit(),
+            ["consolidate"] => self.consolidate(),
+            ["reload"] => self.reload_config(),
+            [set @ ("set" | "set!"), key, value @ ..] => {
+                self.set_option(key, &value.join(" "), *set == "set!")
+            }
+            ["set" | "set!"] => self.set_status(Severity::Warning, "Usage: :set <option> [value]"),
+            ["source", path @ ..] => {
+                if !self.is_locked() {
+                    self.source_file(&path.join(" "));
+                }
+            }
+            ["move", app_name @ ..] => {
+                if !self.is_locked() {
+                    self.move_selected_to_app(app_name.join(" "));
+                }
+            }
+            ["keys", keys @ ..] => {
+                if !self.is_locked() {
+                    self.set_selected_keys(keys.join(" "));
+                }
+            }
+            ["color"] => {
+                if !self.is_locked() {
+                    self.set_color("");
+                }
+            }
+            ["color", color] => {
+                if !self.is_locked() {
+                    self.set_color(color);
+                }
+            }
+            ["note", text @ ..] => {
+                if !self.is_locked() {
+                    self.set_note(text.join(" "));
+                }
+            }
+            ["starred"] => self.toggle_starred_view(),
+            ["only"] | ["only", "all"] => {
+                self.modifier_filter = None;
+                self.refilter();
+                self.set_status(Severity::Info, "Showing all keybinds.");
+            }
+            ["only", name] => match ModifierFilter::parse(name) {
+                Some(filter) => {
+                    self.modifier_filter = Some(filter);
+                    self.refilter();
+                    self.set_status(
+                        Severity::Info,
+                        format!(
+                            "Showing {} keybind(s) with modifier '{}'.",
+                            self.filtered_items.len(),
+                            filter.name()
+                        ),
+                    );
+                }
+                None => {
+                    self.set_status(
+                        Severity::Error,
+                        format!(
+                            "Error: Unknown modifier '{}'. Use ctrl, alt, shift, none or all.",
+                            name
+                        ),
+                    );
+                }
+            },
+            ["clean"] => {
+                if !self.is_locked() {
+                    self.clean_current_app();
+                }
+            }
+            ["datadir"] => self.open_dir(&get_data_dir()),
+            ["configdir"] => self.open_dir(&get_config_dir()),
+            ["config"] => self.open_config_file(),
+            ["sort", "uses"] => {
+                if !self.is_locked() {
+                    self.sort_by_uses();
+                }
+            }
+            ["sort", ..] => self.set_status(Severity::Warning, "Usage: :sort uses"),
+            ["header", text @ ..] => {
+                if !self.is_locked() {
+                    self.insert_header(text.join(" "));
+                }
+            }
+            ["merge", ..] => {
+                if !self.is_locked() {
+                    let args = split_quoted(
+                        command
+                            .trim_start()
+                            .strip_prefix("merge")
+                            .unwrap_or_default(),
+                    );
+                    match args.as_slice() {
+                        [source, dest] => self.merge_apps(source, dest),
+                        _ => self.set_status(
+                            Severity::Warning,
+                            "Usage: :merge <source> <dest> (quote names with spaces)",
+                        ),
+                    }
+                }
+            }
+            ["grep"] => self.set_status(Severity::Warning, "Usage: :grep <pattern>"),
+            ["grep", pattern @ ..] => self.grep(&pattern.join(" ")),
+            ["export"] => self.open_export_menu(),
+            ["import"] => self.open_import_menu(),
+            ["add"] => {
+                if !self.is_locked() {
+                    self.append_new_keybind();
+                }
+            }
+            ["searchscope", scope] => match SearchScope::parse(scope) {
+                Some(scope) => {
+                    self.search_scope = scope;
+                    self.refilter();
+                    self.set_status(Severity::Info, format!("Searching {}.", scope.name()));
+                }
+                None => self.set_status(Severity::Warning, "Usage: :searchscope keys|desc|both"),
+            },
+            ["searchscope", ..] => self.set_status(
+                Severity::Info,
+                format!(
+                    "Searching {}. Usage: :searchscope keys|desc|both",
+                    self.search_scope.name()
+                ),
+            ),
+            ["relevance"] => {
+                self.config.sort_by_relevance = !self.config.sort_by_relevance;
+                self.refilter();
+                self.set_status(
+                    Severity::Info,
+                    if self.config.sort_by_relevance {
+                        "Sorting search results by relevance."
+                    } else {
+                        "Keeping search results in list order."
+                    },
+                );
+            }
+            ["compact"] => {
+                self.config.compact = !self.config.compact;
+                self.set_status(
+                    Severity::Info,
+                    if self.config.compact {
+                        "Compact view on."
+                    } else {
+                        "Compact view off."
+                    },
+                );
+            }
+            ["nohl"] => {
+                self.highlight_enabled = false;
+            }
+            ["hide-empty"] => {
+                self.hide_empty = true;
+                self.refilter();
+                self.set_status(Severity::Info, "Hiding empty keybinds.");
+            }
+            ["show-empty"] => {
+                self.hide_empty = false;
+                self.refilter();
+                self.set_status(Severity::Info, "Showing empty keybinds.");
+            }
+            ["stats"] => self.show_stats(),
+            ["lint"] => self.select_next_undescribed(),
+            ["toggle"] => {
+                if !self.is_locked() {
+                    self.toggle_selected_enabled();
+                }
+            }
+            ["yank"] => self.yank_selected_row(),
+            ["yank-json"] => self.yank_app_json(),
+            ["paste-json"] => {
+                if !self.is_locked() {
+                    self.paste_json();
+                }
+            }
+            ["only-app"] => {
+                self.single_app = true;
+                self.set_status(
+                    Severity::Info,
+                    format!("Showing only {}.", self.current_application),
+                );
+            }
+            ["all-apps"] => {
+                self.single_app = false;
+                self.refilter();
+                self.set_status(Severity::Info, "App switching re-enabled.");
+            }
+            ["lock"] => {
+                self.locked = true;
+                self.set_status(Severity::Info, "Locked. Editing is disabled.");
+            }
+            ["unlock"] => {
+                self.locked = false;
+                self.set_status(Severity::Info, "Unlocked.");
+            }
+            ["history"] => {
+                self.history_selected_index = 0;
+                self.mode = Mode::History;
+            }
+            ["help"] => {
+                self.mode = Mode::Help;
+            }
+            ["messages"] => {
+                self.mode = Mode::Messages;
+            }
+            ["failed"] => {
+                if self.load_failures.is_empty() {
+                    self.set_status(Severity::Info, "All data files loaded.");
+                } else {
+                    self.mode = Mode::LoadFailures;
+                }
+            }
+            ["diff"] => {
+                let app_name = self.current_application.clone();
+                match self.read_saved_apps() {
+                    Ok(saved_apps) => {
+                        // An application that was never saved diffs against
+                        // an empty list.
+                        let saved = saved_apps
+                            .into_iter()
+                            .find(|app| app.application == app_name)
+                            .map(|app| app.keybinds)
+                            .unwrap_or_default();
+                        let current = self.app_keybinds(&app_name).keybinds;
+                        self.diff_lines = diff_keybind_entries(&saved, &current);
+                        self.mode = Mode::Diff;
+                    }
+                    Err(e) => self.set_status(Severity::Error, e),
+                }
+            }
+            ["revert"] | ["close"] => {
+                if !self.is_locked() {
+                    self.revert_current_app(false);
+                }
+            }
+            ["revert!"] | ["close!"] => {
+                if !self.is_locked() {
+                    self.revert_current_app(true);
+                }
+            }
+            ["saveas", ..] if self.is_single_app() => {}
+            ["saveas", name @ ..] => self.save_as(name.join(" ")),
+            ["new", ..] if self.is_single_app() => {}
+            ["new", app_name @ ..] => {
+                let app_name_str = app_name.join(" ");
+                if let Some(other) = self
+                    .file_name_collision(&app_name_str)
+                    .filter(|_| self.config.storage_mode == StorageMode::PerApp)
+                {
+                    self.set_status(
+                        Severity::Warning,
+                        format!(
+                            "'{}' would be saved to the same file as '{}' ({}).",
+                            app_name_str,
+                            other,
+                            app_file_name(&app_name_str)
+                        ),
+                    );
+                } else if !app_name_str.is_empty() && !self.all_applications.contains(&app_name_str)
+                {
+                    self.push_to_undo_history(&format!("New app {}", app_name_str));
+                    self.all_applications.insert(app_name_str.clone());
+                    self.switch_to_app(app_name_str);
+                    self.set_status(
+                        Severity::Success,
+                        format!("Created new app '{}'.", self.current_application),
+                    );
+                    if self.config.auto_insert_on_new && !self.locked {
+                        self.insert_new_keybind(false);
+                    }
+                } else {
+                    self.set_status(Severity::Warning, "App name invalid or already exists.");
+                }
+            }
+            [line] if line.parse::<usize>().is_ok() => {
+                let line: usize = line.parse().unwrap_or_default();
+                let last_row = self.filtered_items.len().saturating_sub(1);
+                self.selected_cell.0 = line.saturating_sub(1).min(last_row);
+            }
+            _ => self.set_status(Severity::Warning, format!("Not a command: {}", command)),
+        }
+    }
+}
+
+/// Lists the keybinds added, removed and modified going from `saved` to
+/// `current`. A removed and an added entry with the same keys are reported as
+/// one modification.
+fn diff_keybind_entries(saved: &[KeybindEntry], current: &[KeybindEntry]) -> Vec<DiffLine> {
+    // Entries present on both sides are unchanged and dropped first.
+    let mut removed: Vec<&KeybindEntry> = saved.iter().collect();
+    let mut added = Vec::new();
+    for entry in current {
+        match removed.iter().position(|&kb| kb == entry) {
+            Some(pos) => {
+                removed.remove(pos);
+            }
+            None => added.push(entry),
+        }
+    }
+
+    let mut lines = Vec::new();
+    for entry in added {
+        match removed.iter().position(|kb| kb.keys == entry.keys) {
+            Some(pos) => {
+                let old = removed.remove(pos);
+                lines.push(DiffLine::Modified {
+                    old: old.clone(),
+                    new: entry.clone(),
+                });
+            }
+            None => lines.push(DiffLine::Added(entry.clone())),
+        }
+    }
+    lines.extend(removed.into_iter().map(|kb| DiffLine::Removed(kb.clone())));
+    lines
+}
+
+/// Counts how many keybinds were added and removed going from `from` to `to`.
+fn diff_counts(from: &[Keybind], to: &[Keybind]) -> (usize, usize) {
+    let mut counts: HashMap<&Keybind, isize> = HashMap::new();
+    for kb in to {
+        *counts.entry(kb).or_default() += 1;
+    }
+    for kb in from {
+        *counts.entry(kb).or_default() -= 1;
+    }
+    let added = counts
+        .values()
+        .filter(|&&c| c > 0)
+        .map(|&c| c as usize)
+        .sum();
+    let removed = counts
+        .values()
+        .filter(|&&c| c < 0)
+        .map(|&c| (-c) as usize)
+        .sum();
+    (added, removed)
+}
+
+fn format_elapsed(timestamp: Instant) -> String {
+    let secs = timestamp.elapsed().as_secs();
+    if secs < 60 {
+        format!("{}s ago", secs)
+    } else if secs < 3600 {
+        format!("{}m ago", secs / 60)
+    } else {
+        format!("{}h ago", secs / 3600)
+    }
+}
+
+/// MIME type registered by `--register` for keybind JSON files.
+#[cfg(target_os = "linux")]
+const MIME_TYPE: &str = "application/x-ctrlset+json";
+
+/// Installs a freedesktop `.desktop` entry and a MIME type for keybind files
+/// under `~/.local/share`, and makes ctrlset their default handler.
+#[cfg(target_os = "linux")]
+fn register_desktop_entry() -> Result<(), String> {
+    let base_dirs = BaseDirs::new().ok_or("Could not find the home directory.")?;
+    let share_dir = base_dirs.data_dir();
+    let exe = std::env::current_exe()
+        .map_err(|e| format!("Could not find the ctrlset executable: {}", e))?;
+
+    let applications_dir = share_dir.join("applications");
+    let desktop_entry = format!(
+        "[Desktop Entry]\n\
+         Type=Application\n\
+         Name=ctrlset\n\
+         Comment=Keep track of your keybindings\n\
+         Exec=\"{}\" --import %f\n\
+         Terminal=false\n\
+         Categories=Utility;\n\
+         MimeType={};\n",
+        exe.display(),
+        MIME_TYPE
+    );
+    fs::create_dir_all(&applications_dir)
+        .and_then(|_| fs::write(applications_dir.join("ctrlset.desktop"), desktop_entry))
+        .map_err(|e| format!("Failed to write the desktop entry: {}", e))?;
+
+    // Keybind files are plain JSON, so they're recognised by their leading
+    // "application" field.
+    let mime_dir = share_dir.join("mime");
+    let mime_package = format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<mime-info xmlns="http://www.freedesktop.org/standards/shared-mime-info">
+  <mime-type type="{}">
+    <comment>ctrlset keybinds</comment>
+    <sub-class-of type="application/json"/>
+    <magic priority="60">
+      <match type="string" value="&quot;application&quot;" offset="0:64"/>
+    </magic>
+  </mime-type>
+</mime-info>
+"#,
+        MIME_TYPE
+    );
+    fs::create_dir_all(mime_dir.join("packages"))
+        .and_then(|_| fs::write(mime_dir.join("packages/ctrlset.xml"), mime_package))
+        .map_err(|e| format!("Failed to write the MIME type: {}", e))?;
+
+    // These only refresh caches, so a missing tool isn't fatal.
+    let commands: [(&str, Vec<&std::ffi::OsStr>); 3] = [
+        ("update-mime-database", vec![mime_dir.as_os_str()]),
+        (
+            "update-desktop-database",
+            vec![applications_dir.as_os_str()],
+        ),
+        (
+            "xdg-mime",
+            vec![
+                "default".as_ref(),
+                "ctrlset.desktop".as_ref(),
+                MIME_TYPE.as_ref(),
+            ],
+        ),
+    ];
+    for (program, args) in commands {
+        if let Err(e) = std::process::Command::new(program).args(args).status() {
+            eprintln!("Warning: Could not run {}: {}", program, e);
+        }
+    }
+    Ok(())
+}
+
+fn main() -> Result<(), eframe::Error> {
+    let args: Vec<String> = std::env::args().collect();
+    let debug_mode = args.contains(&"--debug".to_string());
+
+    #[cfg(target_os = "linux")]
+    if args.contains(&"--register".to_string()) {
+        match register_desktop_entry() {
+            Ok(()) => println!("Registered ctrlset as the handler for keybind files."),
+            Err(e) => eprintln!("Error: {}", e),
+        }
+        return Ok(());
+    }
+    let import_path = args
+        .iter()
+        .position(|arg| arg == "--import")
+        .and_then(|i| args.get(i + 1))
+        .cloned();
+    let single_app = args
+        .iter()
+        .position(|arg| arg == "--app")
+        .and_then(|i| args.get(i + 1))
+        .cloned();
+    if let Some(dir) = args
+        .iter()
+        .position(|arg| arg == "--data-dir")
+        .and_then(|i| args.get(i + 1))
+    {
+        let _ = DATA_DIR_OVERRIDE.set(PathBuf::from(dir));
+    }
+
+    let options = eframe::NativeOptions {
+        viewport: egui::ViewportBuilder::default().with_inner_size([800.0, 600.0]),
+        ..Default::default()
+    };
+    eframe::run_native(
+        "ctrlset",
+        options,
+        Box::new(move |cc| {
+            // Zoom is handled by `handle_global_input` so it can be saved.
+            cc.egui_ctx.options_mut(|o| o.zoom_with_keyboard = false);
+            Box::new(App::new(debug_mode, import_path, single_app))
+        }),
+    )
+}
+
+struct App {
+    state: AppState,
+}
+impl App {
+    fn new(debug_mode: bool, import_path: Option<String>, single_app: Option<String>) -> Self {
+        let mut state = AppState::new(debug_mode);
+        if let Some(path) = import_path {
+            state.source_file(&path);
+        }
+        if let Some(app_name) = single_app {
+            state.all_applications.insert(app_name.clone());
+            state.switch_to_app(app_name);
+            state.single_app = true;
+        }
+        Self { state }
+    }
+}
+
+impl eframe::App for App {
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        let state = &mut self.state;
+
+        let title = if state.dirty {
+            "ctrlset [*]"
+        } else {
+            "ctrlset"
+        };
+        ctx.send_viewport_cmd(egui::ViewportCommand::Title(title.to_string()));
+
+        if ctx.zoom_factor() != state.config.zoom {
+            ctx.set_zoom_factor(state.config.zoom);
+        }
+
+        if state.is_listening_for_keybind {
+            handle_key_capture(ctx, state);
+        } else {
+            handle_global_input(ctx, state);
+        }
+
+        #[cfg(feature = "share")]
+        state.poll_share_upload(ctx);
+
+        if let Some(text) = state.clipboard_text.take() {
+            state.write_clipboard(ctx, text);
+        }
+
+        if state.should_quit {
+            ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+        }
+
+        egui::CentralPanel::default().show(ctx, |ui| {
+            ui.with_layout(Layout::top_down(egui::Align::Center), |ui| {
+                if !state.config.compact {
+                    ui.add_space(20.0);
+                }
+                Frame::group(ui.style()).show(ui, |ui| {
+                    draw_main_table(ui, state);
+                });
+            });
+        });
+
+        egui::TopBottomPanel::bottom("status_bar").show(ctx, |ui| {
+            draw_status_bar(ui, state);
+        });
+
+        match state.mode {
+            Mode::AppFilter => draw_app_filter_popup(ctx, state),
+            Mode::Export => draw_export_popup(ctx, state),
+            Mode::Import => draw_import_popup(ctx, state),
+            Mode::Help => draw_help_popup(ctx, state),
+            Mode::History => draw_history_popup(ctx, state),
+            Mode::CommandPalette => draw_command_palette(ctx, state),
+            Mode::ImportMatch => draw_import_match_popup(ctx, state),
+            Mode::Messages => draw_messages_popup(ctx, state),
+            Mode::Diff => draw_diff_popup(ctx, state),
+            Mode::LoadFailures => draw_load_failures_popup(ctx, state),
+            Mode::Grep => draw_grep_popup(ctx, state),
+            Mode::QuitConfirm => draw_quit_confirm_popup(ctx, state),
+            Mode::ForceQuitConfirm => draw_force_quit_confirm_popup(ctx, state),
+            _ => {}
+        }
+
+        state.log_status_message();
+        // Wake up to clear the status message even without input.
+        if state.config.status_timeout_secs > 0.0 && !state.status_message.is_empty() {
+            ctx.request_repaint_after(std::time::Duration::from_secs_f32(
+                state.config.status_timeout_secs,
+            ));
+        }
+    }
+}
+
+/// Records the keys pressed while the keybind column is being edited. egui
+/// only reports logical keys and modifier flags, so numpad keys come out as
+/// their main keyboard equivalents and left and right modifiers can't be told
+/// apart; `:keys` is the way to write those down.
+fn handle_key_capture(ctx: &Context, state: &mut AppState) {
+    if state.ignore_next_input_frame {
+        state.ignore_next_input_frame = false;
+        return;
+    }
+
+    ctx.input(|i| {
+        if i.key_pressed(Key::Escape) {
+            // Cancelling keeps the keys the cell had before capturing.
+            state.captured_keys = None;
+            state.exit_insert_mode(true);
+            return;
+        }
+        let notation = state.config.keybind_notation;
+        // (chord, whether it confirms the capture, whether it's a scroll)
+        let pressed = i.events.iter().find_map(|e| match e {
+            egui::Event::Key {
+                key, pressed: true, ..
+            } if !is_key_just_a_modifier(*key) => Some((
+                format_key(i.modifiers, *key, notation),
+                *key == Key::Enter && i.modifiers.is_none(),
+                false,
+            )),
+            egui::Event::PointerButton {
+                button,
+                pressed: true,
+                modifiers,
+                ..
+            } => Some((
+                format_mouse(*modifiers, MouseInput::from_button(*button), notation),
+                false,
+                false,
+            )),
+            egui::Event::MouseWheel {
+                delta, modifiers, ..
+            } => MouseInput::from_scroll(*delta)
+                .map(|input| (format_mouse(*modifiers, input, notation), false, true)),
+            _ => None,
+        });
+
+        if let Some((chord, confirms, is_scroll)) = pressed {
+            let delimiter = &state.config.chord_delimiter;
+            match state.captured_keys.take() {
+                Some(captured) if confirms => {
+                    state.temp_edit_buffer = captured;
+                    state.exit_insert_mode(true);
+                }
+                // One turn of the wheel or swipe of a trackpad sends a burst
+                // of scroll events.
+                Some(captured)
+                    if is_scroll
+                        && (captured == chord
+                            || captured.ends_with(&format!("{}{}", delimiter, chord))) =>
+                {
+                    state.captured_keys = Some(captured);
+                }
+                Some(captured) if state.capturing_sequence => {
+                    state.captured_keys = Some(format!("{}{}{}", captured, delimiter, chord));
+                }
+                _ => state.captured_keys = Some(chord),
+            }
+        }
+    });
+}
+
+/// A mouse button or scroll direction captured as part of a keybind, such as
+/// `Ctrl+ScrollUp`.
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum MouseInput {
+    Left,
+    Right,
+    Middle,
+    Back,
+    Forward,
+    ScrollUp,
+    ScrollDown,
+    ScrollLeft,
+    ScrollRight,
+}
+
+impl MouseInput {
+    const ALL: [MouseInput; 9] = [
+        MouseInput::Left,
+        MouseInput::Right,
+        MouseInput::Middle,
+        MouseInput::Back,
+        MouseInput::Forward,
+        MouseInput::ScrollUp,
+        MouseInput::ScrollDown,
+        MouseInput::ScrollLeft,
+        MouseInput::ScrollRight,
+    ];
+
+    fn from_button(button: egui::PointerButton) -> Self {
+        match button {
+            egui::PointerButton::Primary => MouseInput::Left,
+            egui::PointerButton::Secondary => MouseInput::Right,
+            egui::PointerButton::Middle => MouseInput::Middle,
+            egui::PointerButton::Extra1 => MouseInput::Back,
+            egui::PointerButton::Extra2 => MouseInput::Forward,
+        }
+    }
+
+    /// The scroll direction of a wheel `delta`, going by its larger axis.
+    /// Positive values move the content down or right, i.e. scroll up or left.
+    fn from_scroll(delta: egui::Vec2) -> Option<Self> {
+        if delta.x.abs() > delta.y.abs() {
+            Some(if delta.x > 0.0 {
+                MouseInput::ScrollLeft
+            } else {
+                MouseInput::ScrollRight
+            })
+        } else if delta.y > 0.0 {
+            Some(MouseInput::ScrollUp)
+        } else if delta.y < 0.0 {
+            Some(MouseInput::ScrollDown)
+        } else {
+            None
+        }
+    }
+
+    /// The name in egui notation, e.g. `MouseMiddle`.
+    fn name(self) -> &'static str {
+        match self {
+            MouseInput::Left => "MouseLeft",
+            MouseInput::Right => "MouseRight",
+            MouseInput::Middle => "MouseMiddle",
+            MouseInput::Back => "MouseBack",
+            MouseInput::Forward => "MouseForward",
+            MouseInput::ScrollUp => "ScrollUp",
+            MouseInput::ScrollDown => "ScrollDown",
+            MouseInput::ScrollLeft => "ScrollLeft",
+            MouseInput::ScrollRight => "ScrollRight",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|input| input.name() == name)
+    }
+
+    fn emacs_name(self) -> &'static str {
+        match self {
+            MouseInput::Left => "mouse-1",
+            MouseInput::Middle => "mouse-2",
+            MouseInput::Right => "mouse-3",
+            MouseInput::Back => "mouse-8",
+            MouseInput::Forward => "mouse-9",
+            MouseInput::ScrollUp => "wheel-up",
+            MouseInput::ScrollDown => "wheel-down",
+            MouseInput::ScrollLeft => "wheel-left",
+            MouseInput::ScrollRight => "wheel-right",
+        }
+    }
+
+    fn vim_name(self) -> &'static str {
+        match self {
+            MouseInput::Left => "LeftMouse",
+            MouseInput::Right => "RightMouse",
+            MouseInput::Middle => "MiddleMouse",
+            MouseInput::Back => "X1Mouse",
+            MouseInput::Forward => "X2Mouse",
+            MouseInput::ScrollUp => "ScrollWheelUp",
+            MouseInput::ScrollDown => "ScrollWheelDown",
+            MouseInput::ScrollLeft => "ScrollWheelLeft",
+            MouseInput::ScrollRight => "ScrollWheelRight",
+        }
+    }
+
+    fn symbol(self) -> &'static str {
+        match self {
+            MouseInput::Left => "LMB",
+            MouseInput::Right => "RMB",
+            MouseInput::Middle => "MMB",
+            MouseInput::Back => "MB4",
+            MouseInput::Forward => "MB5",
+            MouseInput::ScrollUp => "Wheel↑",
+            MouseInput::ScrollDown => "Wheel↓",
+            MouseInput::ScrollLeft => "Wheel←",
+            MouseInput::ScrollRight => "Wheel→",
+        }
+    }
+}
+
+/// The modifiers of a chord in egui notation, e.g. `["Ctrl", "Shift"]`.
+fn egui_modifier_names(mods: Modifiers) -> Vec<&'static str> {
+    let mut names = Vec::new();
+    if mods.ctrl {
+        names.push("Ctrl");
+    }
+    if mods.alt {
+        names.push("Alt");
+    }
+    if mods.shift {
+        names.push("Shift");
+    }
+    if mods.mac_cmd {
+        names.push("Cmd");
+    }
+    names
+}
+
+/// The modifier prefix of a chord in the emacs or vim notation, e.g. `C-S-`.
+/// `shift` is false when Shift is written as an uppercase letter instead.
+fn modifier_prefix(mods: Modifiers, shift: bool, emacs: bool) -> String {
+    let mut prefix = String::new();
+    if mods.ctrl {
+        prefix.push_str("C-");
+    }
+    if mods.alt {
+        prefix.push_str("M-");
+    }
+    if shift {
+        prefix.push_str("S-");
+    }
+    if mods.mac_cmd {
+        prefix.push_str(if emacs { "s-" } else { "D-" });
+    }
+    prefix
+}
+
+/// Formats a captured mouse button or scroll in the given notation, e.g.
+/// `Ctrl+ScrollUp`, `C-<wheel-up>` or `<C-ScrollWheelUp>`.
+fn format_mouse(mods: Modifiers, input: MouseInput, notation: KeybindNotation) -> String {
+    match notation {
+        KeybindNotation::Egui => {
+            let mut parts = egui_modifier_names(mods);
+            parts.push(input.name());
+            parts.join("+")
+        }
+        KeybindNotation::Emacs => format!(
+            "{}<{}>",
+            modifier_prefix(mods, mods.shift, true),
+            input.emacs_name()
+        ),
+        KeybindNotation::Vim => format!(
+            "<{}{}>",
+            modifier_prefix(mods, mods.shift, false),
+            input.vim_name()
+        ),
+    }
+}
+
+/// Formats a captured key press in the given notation. In the emacs and vim
+/// notations Shift on a letter is written as the uppercase letter.
+fn format_key(mods: Modifiers, key: Key, notation: KeybindNotation) -> String {
+    if notation == KeybindNotation::Egui {
+        let name = format!("{:?}", key);
+        let mut parts = egui_modifier_names(mods);
+        parts.push(&name);
+        return parts.join("+");
+    }
+
+    let name = key.name();
+    let is_letter = name.len() == 1 && name.chars().all(|c| c.is_ascii_alphabetic());
+    let shift_in_name = mods.shift && is_letter;
+    let emacs = notation == KeybindNotation::Emacs;
+
+    let prefix = modifier_prefix(mods, mods.shift && !shift_in_name, emacs);
+
+    let key_name = match (key, emacs) {
+        (Key::Enter, true) => "RET".to_string(),
+        (Key::Escape, true) => "ESC".to_string(),
+        (Key::Tab, true) => "TAB".to_string(),
+        (Key::Space, true) => "SPC".to_string(),
+        (Key::Backspace, true) => "DEL".to_string(),
+        (Key::Enter, false) => "CR".to_string(),
+        (Key::Escape, false) => "Esc".to_string(),
+        (Key::Backspace, false) => "BS".to_string(),
+        (Key::Delete, false) => "Del".to_string(),
+        _ if shift_in_name => name.to_string(),
+        _ if is_letter => name.to_lowercase(),
+        _ if emacs && name.chars().count() > 1 => format!("<{}>", name.to_lowercase()),
+        _ => name.to_string(),
+    };
+
+    if !emacs && (!prefix.is_empty() || key_name.chars().count() > 1) {
+        format!("<{}{}>", prefix, key_name)
+    } else {
+        format!("{}{}", prefix, key_name)
+    }
+}
+
+/// Parses a keys string written by `format_key` in egui notation, such as
+/// `Ctrl+Shift+X`.
+fn parse_egui_keys(keys: &str) -> Option<(Modifiers, Key)> {
+    let (mods, key_name) = parse_egui_chord(keys)?;
+    let key = Key::from_name(key_name)
+        .or_else(|| key_name.strip_prefix("Num").and_then(Key::from_name))?;
+    Some((mods, key))
+}
+
+/// Parses a mouse chord written by `format_mouse` in egui notation, such as
+/// `Ctrl+MouseMiddle`.
+fn parse_egui_mouse(keys: &str) -> Option<(Modifiers, MouseInput)> {
+    let (mods, name) = parse_egui_chord(keys)?;
+    Some((mods, MouseInput::from_name(name)?))
+}
+
+/// Splits an egui notation chord into its modifiers and the name after them.
+fn parse_egui_chord(chord: &str) -> Option<(Modifiers, &str)> {
+    let mut parts: Vec<_> = chord.split('+').collect();
+    let name = parts.pop()?;
+    let mut mods = Modifiers::NONE;
+    for part in parts {
+        match part {
+            "Ctrl" => mods.ctrl = true,
+            "Alt" => mods.alt = true,
+            "Shift" => mods.shift = true,
+            "Cmd" => mods.mac_cmd = true,
+            _ => return None,
+        }
+    }
+    Some((mods, name))
+}
+
+/// Formats a key as symbols, e.g. `⌃⇧↑` for Ctrl+Shift+ArrowUp.
+fn format_key_symbols(mods: Modifiers, key: Key) -> String {
+    let mut text = modifier_symbols(mods);
+    let symbol = match key {
+        Key::ArrowUp => "↑",
+        Key::ArrowDown => "↓",
+        Key::ArrowLeft => "←",
+        Key::ArrowRight => "→",
+        Key::Enter => "⏎",
+        Key::Backspace => "⌫",
+        Key::Delete => "⌦",
+        Key::Tab => "⇥",
+        Key::Escape => "⎋",
+        Key::Space => "␣",
+        Key::PageUp => "⇞",
+        Key::PageDown => "⇟",
+        Key::Home => "↖",
+        Key::End => "↘",
+        _ => key.name(),
+    };
+    text.push_str(symbol);
+    text
+}
+
+/// The symbols for `mods`, listed in the order Mac keyboards print them.
+fn modifier_symbols(mods: Modifiers) -> String {
+    let mut text = String::new();
+    if mods.ctrl {
+        text.push('⌃');
+    }
+    if mods.alt {
+        text.push('⌥');
+    }
+    if mods.shift {
+        text.push('⇧');
+    }
+    if mods.mac_cmd {
+        text.push('⌘');
+    }
+    text
+}
+
+/// Returns `keys` as it should be shown in the configured notation, or as
+/// symbols. Each chord between `delimiter`s is converted on its own; chords
+/// that were not captured in egui notation are shown as written.
+fn display_keys(
+    keys: &str,
+    notation: KeybindNotation,
+    key_display: KeyDisplay,
+    delimiter: &str,
+) -> String {
+    if notation == KeybindNotation::Egui && key_display == KeyDisplay::Raw {
+        return keys.to_string();
+    }
+    let delimiter = if delimiter.is_empty() { " " } else { delimiter };
+    keys.split(delimiter)
+        .map(|chord| {
+            if let Some((mods, input)) = parse_egui_mouse(chord) {
+                return match key_display {
+                    KeyDisplay::Symbols => format!("{}{}", modifier_symbols(mods), input.symbol()),
+                    KeyDisplay::Raw => format_mouse(mods, input, notation),
+                };
+            }
+            match parse_egui_keys(chord) {
+                Some((mods, key)) if key_display == KeyDisplay::Symbols => {
+                    format_key_symbols(mods, key)
+                }
+                Some((mods, key)) => format_key(mods, key, notation),
+                None => chord.to_string(),
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(delimiter)
+}
+
+fn is_key_just_a_modifier(key: Key) -> bool {
+    matches!(
+        key,
+        Key::F1
+            | Key::F2
+            | Key::F3
+            | Key::F4
+            | Key::F5
+            | Key::F6
+            | Key::F7
+            | Key::F8
+            | Key::F9
+            | Key::F10
+            | Key::F11
+            | Key::F12
+            | Key::F13
+            | Key::F14
+            | Key::F15
+            | Key::F16
+            | Key::F17
+            | Key::F18
+            | Key::F19
+            | Key::F20
+    )
+}
+
+fn handle_global_input(ctx: &Context, state: &mut AppState) {
+    if state.debug_mode {
+        ctx.input(|i| {
+            i.events.iter().for_each(|e| {
+                if let egui::Event::Key {
+                    key,
+                    pressed: true,
+                    modifiers,
+                    ..
+                } = e
+                {
+                    println!("DEBUG: Key pressed: {:?}, Modifiers: {:?}", key, modifiers);
+                }
+            })
+        });
+    }
+
+    ctx.input_mut(|i| {
+        if i.consume_key(Modifiers::CTRL, Key::Equals) || i.consume_key(Modifiers::CTRL, Key::Plus)
+        {
+            state.set_zoom(state.config.zoom + ZOOM_STEP);
+        }
+        if i.consume_key(Modifiers::CTRL, Key::Minus) {
+            state.set_zoom(state.config.zoom - ZOOM_STEP);
+        }
+        if i.consume_key(Modifiers::CTRL, Key::Num0) {
+            state.set_zoom(1.0);
+        }
+    });
+
+    match state.mode {
+        Mode::Normal => handle_normal_mode_input(ctx, state),
+        Mode::Insert => handle_insert_mode_input(ctx, state),
+        Mode::Search => handle_search_mode_input(ctx, state),
+        Mode::Command => handle_command_mode_input(ctx, state),
+        Mode::Jump => handle_jump_mode_input(ctx, state),
+        Mode::AppFilter
+        | Mode::Export
+        | Mode::Import
+        | Mode::Help
+        | Mode::History
+        | Mode::CommandPalette
+        | Mode::ImportMatch
+        | Mode::Messages
+        | Mode::Diff
+        | Mode::LoadFailures
+        | Mode::Grep
+        | Mode::QuitConfirm
+        | Mode::ForceQuitConfirm => {}
+    }
+}
+
+fn handle_normal_mode_input(ctx: &Context, state: &mut AppState) {
+    let was_recording = state.recording_macro.is_some();
+    let pressed = if was_recording {
+        ctx.input(|i| macro_events(&i.events))
+    } else {
+        Vec::new()
+    };
+    handle_normal_mode_keys(ctx, state);
+    // The frame with the key that stopped recording isn't part of the macro.
+    if was_recording && state.recording_macro.is_some() {
+        state.macro_buffer.extend(pressed);
+    }
+    if let Some(register) = state.pending_macro.take() {
+        play_macro(ctx, state, register);
+    }
+}
+
+/// The events of a frame worth recording into a macro: key presses, and text
+/// so `@` is seen on any keyboard layout.
+fn macro_events(events: &[egui::Event]) -> Vec<egui::Event> {
+    events
+        .iter()
+        .filter(|e| {
+            matches!(
+                e,
+                egui::Event::Key { pressed: true, .. } | egui::Event::Text(_)
+            )
+        })
+        .cloned()
+        .collect()
+}
+
+/// Consumes the first letter or digit pressed this frame, as the register of
+/// a macro.
+fn consume_register(i: &mut egui::InputState) -> Option<char> {
+    let (key, modifiers) = i.events.iter().find_map(|e| match e {
+        egui::Event::Key {
+            key,
+            pressed: true,
+            modifiers,
+            ..
+        } => Some((*key, *modifiers)),
+        _ => None,
+    })?;
+    let mut chars = key.name().chars();
+    let register = chars.next().filter(|c| c.is_ascii_alphanumeric())?;
+    if chars.next().is_some() || !(modifiers.is_none() || modifiers == Modifiers::SHIFT) {
+        return None;
+    }
+    i.consume_key(modifiers, key);
+    Some(register.to_ascii_lowercase())
+}
+
+/// `@<reg>`: feeds the keys recorded in `register` back through Normal mode
+/// one at a time, stopping early if one of them leaves Normal mode.
+fn play_macro(ctx: &Context, state: &mut AppState, register: char) {
+    if state.replaying_macro {
+        state.set_status(Severity::Warning, "Macros can't replay other macros.");
+        return;
+    }
+    let Some(events) = state.macros.get(&register).cloned() else {
+        state.set_status(
+            Severity::Warning,
+            format!("Nothing recorded in register {}.", register),
+        );
+        return;
+    };
+    state.replaying_macro = true;
+    let live_events = ctx.input_mut(|i| std::mem::take(&mut i.events));
+    for event in events {
+        if state.mode != Mode::Normal {
+            break;
+        }
+        ctx.input_mut(|i| i.events = vec![event]);
+        handle_normal_mode_keys(ctx, state);
+        if state.pending_macro.take().is_some() {
+            state.set_status(Severity::Warning, "Macros can't replay other macros.");
+        }
+    }
+    ctx.input_mut(|i| i.events = live_events);
+    state.replaying_macro = false;
+}
+
+fn handle_normal_mode_keys(ctx: &Context, state: &mut AppState) {
+    ctx.input_mut(|i| {
+        let keymap = state.config.keymap.clone();
+
+        let leader_key = string_to_key(&keymap.leader).unwrap_or(Key::Space);
+        let down_key = string_to_key(&keymap.down).unwrap_or(Key::J);
+        let up_key = string_to_key(&keymap.up).unwrap_or(Key::K);
+
+        // egui only hands over clipboard contents through a paste event, so
+        // `:paste-json` waits for the next Ctrl+V.
+        if state.awaiting_paste {
+            let pasted = i.events.iter().find_map(|e| match e {
+                egui::Event::Paste(text) => Some(text.clone()),
+                _ => None,
+            });
+            if let Some(text) = pasted {
+                state.awaiting_paste = false;
+                state.paste_keybinds(&text);
+                return;
+            }
+            if i.consume_key(Modifiers::NONE, Key::Escape) {
+                state.awaiting_paste = false;
+                state.set_status(Severity::Info, "Paste cancelled.");
+                return;
+            }
+        }
+
+        if state.record_macro_pressed || state.play_macro_pressed {
+            if let Some(register) = consume_register(i) {
+                if state.record_macro_pressed {
+                    state.recording_macro = Some(register);
+                    state.macro_buffer.clear();
+                } else {
+                    state.pending_macro = Some(register);
+                }
+            }
+            if i.events
+                .iter()
+                .any(|e| matches!(e, egui::Event::Key { pressed: true, .. }))
+                || state.recording_macro.is_some()
+                || state.pending_macro.is_some()
+            {
+                state.record_macro_pressed = false;
+                state.play_macro_pressed = false;
+            }
+            return;
+        }
+
+        if state.leader_key_pressed {
+            let mut consumed = false;
+            if i.consume_key(
+                Modifiers::NONE,
+                string_to_key(&keymap.app_filter).unwrap_or(Key::F),
+            ) {
+                if !state.is_single_app() {
+                    state.mode = Mode::AppFilter;
+                }
+                consumed = true;
+            } else if i.consume_key(
+                Modifiers::NONE,
+                string_to_key(&keymap.export_menu).unwrap_or(Key::E),
+            ) {
+                state.open_export_menu();
+                consumed = true;
+            } else if i.consume_key(
+                Modifiers::NONE,
+                string_to_key(&keymap.import_menu).unwrap_or(Key::I),
+            ) {
+                state.open_import_menu();
+                consumed = true;
+            } else if i.consume_key(
+                Modifiers::NONE,
+                string_to_key(&keymap.command_palette).unwrap_or(Key::P),
+            ) {
+                state.palette_selected_index = 0;
+                state.mode = Mode::CommandPalette;
+                consumed = true;
+            } else if i.consume_key(
+                Modifiers::NONE,
+                string_to_key(&keymap.toggle_enabled).unwrap_or(Key::T),
+            ) {
+                if !state.is_locked() {
+                    state.toggle_selected_enabled();
+                }
+                consumed = true;
+            }
+
+            if consumed
+                || i.events
+                    .iter()
+                    .any(|e| matches!(e, egui::Event::Key { pressed: true, .. }))
+            {
+                state.leader_key_pressed = false;
+            }
+            return;
+        }
+
+        if state.delete_leader_pressed {
+            let mut consumed_key = false;
+            let mut original_indices_to_delete = vec![];
+            let current_row = state.selected_cell.0;
+
+            if i.consume_key(
+                Modifiers::NONE,
+                string_to_key(&keymap.delete_leader).unwrap_or(Key::D),
+            ) {
+                if let Some(item) = state.filtered_items.get(current_row) {
+                    original_indices_to_delete.push(item.original_index);
+                }
+                consumed_key = true;
+            } else if i.consume_key(Modifiers::NONE, down_key) {
+                if let Some(item) = state.filtered_items.get(current_row) {
+                    original_indices_to_delete.push(item.original_index);
+                }
+                if let Some(item) = state.filtered_items.get(current_row + 1) {
+                    original_indices_to_delete.push(item.original_index);
+                }
+                consumed_key = true;
+            } else if i.consume_key(Modifiers::NONE, up_key) {
+                if let Some(item) = state.filtered_items.get(current_row) {
+                    original_indices_to_delete.push(item.original_index);
+                }
+                if current_row > 0 {
+                    if let Some(item) = state.filtered_items.get(current_row - 1) {
+                        original_indices_to_delete.push(item.original_index);
+                    }
+                }
+                consumed_key = true;
+            }
+
+            if !original_indices_to_delete.is_empty() {
+                original_indices_to_delete.sort_unstable();
+                original_indices_to_delete.dedup();
+                state.push_to_undo_history(&format!(
+                    "Delete {} keybind(s)",
+                    original_indices_to_delete.len()
+                ));
+                original_indices_to_delete.reverse();
+
+                for index in &original_indices_to_delete {
+                    state.keybinds.remove(*index);
+                }
+
+                state.set_status(
+                    Severity::Success,
+                    format!("{} keybind(s) deleted.", original_indices_to_delete.len()),
+                );
+                state.refilter();
+                state.clamp_selection();
+            }
+
+            if consumed_key
+                || i.events
+                    .iter()
+                    .any(|e| matches!(e, egui::Event::Key { pressed: true, .. }))
+            {
+                state.delete_leader_pressed = false;
+            }
+            return;
+        }
+
+        if state.yank_leader_pressed {
+            let consumed_key = i.consume_key(
+                Modifiers::NONE,
+                string_to_key(&keymap.yank_leader).unwrap_or(Key::Y),
+            );
+            if consumed_key {
+                state.yank_selected_row();
+            }
+            if consumed_key
+                || i.events
+                    .iter()
+                    .any(|e| matches!(e, egui::Event::Key { pressed: true, .. }))
+            {
+                state.yank_leader_pressed = false;
+            }
+            return;
+        }
+
+        if state.quit_leader_pressed {
+            let quit_key = string_to_key(&keymap.quit_leader).unwrap_or(Key::Z);
+            if i.consume_key(Modifiers::SHIFT, quit_key) {
+                state.quit_leader_pressed = false;
+                state.save_and_quit();
+            } else if i.consume_key(Modifiers::SHIFT, Key::Q) {
+                state.quit_leader_pressed = false;
+                state.force_quit();
+            } else if i
+                .events
+                .iter()
+                .any(|e| matches!(e, egui::Event::Key { pressed: true, .. }))
+            {
+                state.quit_leader_pressed = false;
+            }
+            return;
+        }
+
+        if !state.leader_key_pressed && !state.delete_leader_pressed && !state.yank_leader_pressed {
+            if i.consume_key(
+                Modifiers::SHIFT,
+                string_to_key(&keymap.quit_leader).unwrap_or(Key::Z),
+            ) {
+                state.quit_leader_pressed = true;
+                return;
+            }
+            if i.consume_key(Modifiers::NONE, leader_key) {
+                state.leader_key_pressed = true;
+                return;
+            }
+            if i.consume_key(
+                Modifiers::NONE,
+                string_to_key(&keymap.record_macro).unwrap_or(Key::Q),
+            ) && !state.replaying_macro
+            {
+                match state.recording_macro.take() {
+                    Some(register) => {
+                        let count = state.macro_buffer.len();
+                        state
+                            .macros
+                            .insert(register, std::mem::take(&mut state.macro_buffer));
+                        state.set_status(
+                            Severity::Info,
+                            format!("Recorded {} event(s) into register {}.", count, register),
+                        );
+                    }
+                    None => state.record_macro_pressed = true,
+                }
+                return;
+            }
+            if i.events
+                .iter()
+                .any(|e| matches!(e, egui::Event::Text(text) if text == "@"))
+            {
+                state.play_macro_pressed = true;
+                return;
+            }
+            if i.events
+                .iter()
+                .any(|e| matches!(e, egui::Event::Text(text) if text == "*"))
+            {
+                if !state.is_locked() {
+                    state.toggle_selected_starred();
+                }
+                return;
+            }
+            if i.consume_key(
+                Modifiers::NONE,
+                string_to_key(&keymap.delete_leader).unwrap_or(Key::D),
+            ) {
+                if !state.is_locked() {
+                    state.delete_leader_pressed = true;
+                }
+                return;
+            }
+            if i.consume_key(
+                Modifiers::NONE,
+                string_to_key(&keymap.yank_leader).unwrap_or(Key::Y),
+            ) {
+                state.yank_leader_pressed = true;
+                return;
+            }
+        }
+
+        if i.consume_key(
+            Modifiers::SHIFT,
+            string_to_key(&keymap.goto_bottom).unwrap_or(Key::G),
+        ) {
+            state.selected_cell.0 = state.filtered_items.len().saturating_sub(1);
+        }
+        if keymap.goto_top == "G" && i.key_pressed(Key::G) && i.key_down(Key::G) {
+            state.selected_cell.0 = 0;
+        }
+
+        let page = state.visible_rows.max(1) as isize;
+        if i.consume_key(Modifiers::CTRL, Key::D) {
+            state.move_selection_by((page / 2).max(1));
+        }
+        if i.consume_key(Modifiers::CTRL, Key::U) {
+            state.move_selection_by(-(page / 2).max(1));
+        }
+        if i.consume_key(Modifiers::CTRL, Key::F) {
+            state.move_selection_by(page);
+        }
+        if i.consume_key(Modifiers::CTRL, Key::B) {
+            state.move_selection_by(-page);
+        }
+        // Ctrl also matches Ctrl+Shift, so the Shift variant goes first.
+        if i.consume_key(Modifiers::CTRL | Modifiers::SHIFT, Key::Tab) {
+            state.cycle_application(-1);
+        } else if i.consume_key(Modifiers::CTRL, Key::Tab) {
+            state.cycle_application(1);
+        }
+
+        // Shift+J/K also match the plain down/up keys, so check them first.
+        if i.consume_key(
+            Modifiers::SHIFT,
+            string_to_key(&keymap.move_down).unwrap_or(Key::J),
+        ) && !state.is_locked()
+        {
+            let row = state.selected_cell.0;
+            if row + 1 < state.filtered_items.len() {
+                state.move_filtered_row(row, row + 2);
+            }
+        }
+        if i.consume_key(
+            Modifiers::SHIFT,
+            string_to_key(&keymap.move_up).unwrap_or(Key::K),
+        ) && !state.is_locked()
+        {
+            let row = state.selected_cell.0;
+            if row > 0 {
+                state.move_filtered_row(row, row - 1);
+            }
+        }
+
+        if i.consume_key(Modifiers::NONE, down_key) {
+            let num_rows = state.filtered_items.len();
+            if num_rows > 0 {
+                state.selected_cell.0 = (state.selected_cell.0 + 1).min(num_rows - 1);
+            }
+        }
+        if i.consume_key(Modifiers::NONE, up_key) {
+            state.selected_cell.0 = state.selected_cell.0.saturating_sub(1);
+        }
+
+        if keymap
+            .right
+            .iter()
+            .any(|k| i.consume_key(Modifiers::NONE, string_to_key(k).unwrap_or(Key::L)))
+        {
+            state.selected_cell.1 = (state.selected_cell.1 + 1).min(1);
+        }
+        if keymap
+            .left
+            .iter()
+            .any(|k| i.consume_key(Modifiers::NONE, string_to_key(k).unwrap_or(Key::H)))
+        {
+            state.selected_cell.1 = state.selected_cell.1.saturating_sub(1);
+        }
+
+        if i.consume_key(
+            Modifiers::SHIFT,
+            string_to_key(&keymap.insert_sequence).unwrap_or(Key::I),
+        ) && !state.is_locked()
+            && !state.filtered_items.is_empty()
+        {
+            state.push_to_undo_history("Edit keybind");
+            state.selected_cell.1 = 0;
+            state.capturing_sequence = true;
+            state.enter_insert_mode();
+        }
+        if i.consume_key(
+            Modifiers::NONE,
+            string_to_key(&keymap.insert_mode).unwrap_or(Key::I),
+        ) && !state.is_locked()
+        {
+            if !state.just_created_new_keybind {
+                state.push_to_undo_history("Edit keybind");
+            }
+            state.enter_insert_mode();
+        }
+        // Shift+A also matches the plain `insert_text` key, so check it first.
+        if i.consume_key(
+            Modifiers::SHIFT,
+            string_to_key(&keymap.append_line).unwrap_or(Key::A),
+        ) && !state.is_locked()
+        {
+            state.append_new_keybind();
+        }
+        if i.consume_key(
+            Modifiers::NONE,
+            string_to_key(&keymap.insert_text).unwrap_or(Key::A),
+        ) && !state.is_locked()
+            && !state.filtered_items.is_empty()
+        {
+            state.push_to_undo_history("Edit keybind");
+            state.enter_keys_text_mode();
+        }
+        if i.consume_key(
+            Modifiers::NONE,
+            string_to_key(&keymap.search_mode).unwrap_or(Key::Slash),
+        ) {
+            state.mode = Mode::Search;
+            state.search_query.clear();
+            state.highlight_enabled = true;
+            // Drop the previous query's matches right away so they don't
+            // linger until the first character is typed.
+            state.refilter_keeping_selection();
+        }
+        if i.consume_key(Modifiers::SHIFT, Key::Semicolon)
+            || i.consume_key(
+                Modifiers::NONE,
+                string_to_key(&keymap.command_mode).unwrap_or(Key::Colon),
+            )
+        {
+            state.mode = Mode::Command;
+            state.command_buffer.clear();
+        }
+
+        if (i.consume_key(
+            Modifiers::NONE,
+            string_to_key(&keymap.new_line_below).unwrap_or(Key::O),
+        ) || i.consume_key(
+            Modifiers::SHIFT,
+            string_to_key(&keymap.new_line_above).unwrap_or(Key::O),
+        )) && !state.is_locked()
+        {
+            state.insert_new_keybind(i.modifiers.shift);
+        }
+        if i.consume_key(
+            Modifiers::NONE,
+            string_to_key(&keymap.undo).unwrap_or(Key::U),
+        ) && !state.is_locked()
+        {
+            state.undo();
+        }
+        if i.consume_key(
+            Modifiers::NONE,
+            string_to_key(&keymap.mark_used).unwrap_or(Key::M),
+        ) && !state.is_locked()
+        {
+            state.mark_selected_used();
+        }
+        if i.consume_key(
+            Modifiers::NONE,
+            string_to_key(&keymap.jump).unwrap_or(Key::S),
+        ) && !state.filtered_items.is_empty()
+        {
+            state.mode = Mode::Jump;
+            state.jump_prefix = None;
+        }
+    });
+}
+
+/// Jump mode: typing a row's label selects it; anything else cancels.
+fn handle_jump_mode_input(ctx: &Context, state: &mut AppState) {
+    ctx.input_mut(|i| {
+        if i.consume_key(Modifiers::NONE, Key::Escape) {
+            state.mode = Mode::Normal;
+            return;
+        }
+        let typed = i.events.iter().find_map(|e| match e {
+            egui::Event::Text(text) => text.chars().next(),
+            _ => None,
+        });
+        let Some(c) = typed else {
+            return;
+        };
+        // The letter mustn't reach Normal mode's handlers next frame.
+        i.events.clear();
+        let label = match state.jump_prefix.take() {
+            Some(first) => format!("{}{}", first, c),
+            None => c.to_string(),
+        };
+        if let Some((_, row)) = state.jump_targets.iter().find(|(l, _)| *l == label) {
+            state.selected_cell.0 = *row;
+            state.mode = Mode::Normal;
+        } else if state
+            .jump_targets
+            .iter()
+            .any(|(l, _)| l.starts_with(&label))
+        {
+            state.jump_prefix = Some(c);
+        } else {
+            state.mode = Mode::Normal;
+        }
+    });
+}
+
+/// Labels for `count` jump targets: single letters while they suffice,
+/// otherwise two letters each.
+fn jump_labels(count: usize) -> Vec<String> {
+    let chars: Vec<char> = JUMP_LABEL_CHARS.chars().collect();
+    if count <= chars.len() {
+        return chars.iter().take(count).map(|c| c.to_string()).collect();
+    }
+    chars
+        .iter()
+        .flat_map(|&first| {
+            chars
+                .iter()
+                .map(move |&second| format!("{}{}", first, second))
+        })
+        .take(count)
+        .collect()
+}
+
+/// Labels the rows visible in the table while jumping and remembers them for
+/// `handle_jump_mode_input`.
+fn draw_jump_labels(ui: &Ui, state: &mut AppState, rows: &[(usize, egui::Rect)]) {
+    let visible: Vec<&(usize, egui::Rect)> = rows
+        .iter()
+        .filter(|(_, rect)| ui.clip_rect().contains_rect(*rect))
+        .collect();
+    let labels = jump_labels(visible.len());
+    let font = egui::FontId::monospace(state.config.table_font().size);
+    state.jump_targets.clear();
+    for ((row, rect), label) in visible.into_iter().zip(labels) {
+        // After a first letter, only the labels starting with it are shown,
+        // without it.
+        let shown = match state.jump_prefix {
+            Some(first) if label.starts_with(first) => label[first.len_utf8()..].to_string(),
+            Some(_) => {
+                state.jump_targets.push((label, *row));
+                continue;
+            }
+            None => label.clone(),
+        };
+        let galley = ui
+            .painter()
+            .layout_no_wrap(shown, font.clone(), Color32::BLACK);
+        let label_rect = egui::Rect::from_min_size(rect.left_top(), galley.size()).expand(1.0);
+        ui.painter().rect_filled(label_rect, 2.0, Color32::YELLOW);
+        ui.painter()
+            .galley(label_rect.min + vec2(1.0, 1.0), galley, Color32::BLACK);
+        state.jump_targets.push((label, *row));
+    }
+}
+
+fn handle_insert_mode_input(ctx: &Context, state: &mut AppState) {
+    if state.is_listening_for_keybind {
+        return;
+    }
+    let mut move_cursor_to = None;
+    let multiline = state.editing_multiline();
+    ctx.input_mut(|i| {
+        // Left for the multi-line text edit to insert a newline.
+        if multiline && i.modifiers.shift && i.key_pressed(Key::Enter) {
+            return;
+        }
+        if i.consume_shortcut(&KeyboardShortcut::new(Modifiers::NONE, Key::Escape))
+            || i.consume_shortcut(&KeyboardShortcut::new(Modifiers::CTRL, Key::OpenBracket))
+            || i.consume_shortcut(&KeyboardShortcut::new(Modifiers::NONE, Key::Enter))
+        {
+            state.exit_insert_mode(true);
+            return;
+        }
+        // The text edit only has these on macOS; elsewhere Ctrl+A selects
+        // all. It handles Ctrl+W, Ctrl+U and Ctrl+K itself everywhere.
+        if i.consume_key(Modifiers::CTRL, Key::A) {
+            move_cursor_to = Some(0);
+        } else if i.consume_key(Modifiers::CTRL, Key::E) {
+            move_cursor_to = Some(state.temp_edit_buffer.chars().count());
+        }
+    });
+    if let Some(index) = move_cursor_to {
+        let id = egui::Id::new(CELL_EDIT_ID);
+        let mut edit_state = egui::TextEdit::load_state(ctx, id).unwrap_or_default();
+        edit_state
+            .cursor
+            .set_char_range(Some(egui::text::CCursorRange::one(
+                egui::text::CCursor::new(index),
+            )));
+        edit_state.store(ctx, id);
+    }
+}
+
+fn handle_search_mode_input(ctx: &Context, state: &mut AppState) {
+    ctx.input(|i| {
+        if i.key_pressed(Key::Escape) {
+            state.mode = Mode::Normal;
+            state.search_query.clear();
+            state.refilter_keeping_selection();
+        } else if i.key_pressed(Key::Enter) {
+            state.mode = Mode::Normal;
+        } else if i.key_pressed(Key::Backspace) {
+            if state.search_query.is_empty() {
+                state.mode = Mode::Normal;
+            } else {
+                state.search_query.pop();
+            }
+            state.refilter_keeping_selection();
+        }
+    });
+
+    let mut query_changed = false;
+    for event in &ctx.input(|i| i.events.clone()) {
+        if let egui::Event::Text(text) = event {
+            state.search_query.push_str(text);
+            query_changed = true;
+        }
+    }
+    if query_changed {
+        state.refilter_keeping_selection();
+    }
+}
+
+fn handle_command_mode_input(ctx: &Context, state: &mut AppState) {
+    ctx.input_mut(|i| {
+        if i.key_pressed(Key::Escape) {
+            state.mode = Mode::Normal;
+            state.command_buffer.clear();
+        }
+        // Consume Enter so a popup opened by the command doesn't also see it.
+        if i.consume_key(Modifiers::NONE, Key::Enter) {
+            let command = std::mem::take(&mut state.command_buffer);
+            state.run_command(&command);
+        }
+    });
+}
+
+fn draw_main_table(ui: &mut Ui, state: &mut AppState) {
+    let row_height = ui.fonts(|f| f.row_height(&state.config.table_font()));
+    let row_spacing = state.config.table_row_spacing();
+    // `show_rows` assumes rows are separated by the item spacing, so keep it in
+    // step with the grid's row spacing.
+    ui.spacing_mut().item_spacing.y = row_spacing;
+    let row_height_with_spacing = row_height + row_spacing;
+
+    if let Some(notes) = state.app_notes.get(&state.current_application) {
+        ui.label(RichText::new(notes).italics().weak());
+        ui.separator();
+    }
+
+    if state.filtered_items.is_empty()
+        && !state
+            .keybinds
+            .iter()
+            .any(|kb| kb.application == state.current_application)
+    {
+        draw_empty_state(ui, &state.config.keymap);
+        return;
+    }
+
+    let selected_row = state.selected_cell.0;
+    let selection_moved = state.last_scrolled_row != Some(selected_row);
+    state.last_scrolled_row = Some(selected_row);
+    // Recomputed by the row under the pointer while a drag is in progress.
+    state.drag_target = None;
+
+    let num_columns = if line_numbers_shown(&state.config) {
+        4
+    } else {
+        3
+    };
+
+    let output = if state.config.variable_row_heights() {
+        // Wrapped and multi-line rows vary in height, so every row is laid out and the
+        // selected one is scrolled to by its actual rect.
+        egui::ScrollArea::vertical()
+            .auto_shrink([true, false])
+            .show(ui, |ui| {
+                egui::Grid::new("keybinds_grid")
+                    .num_columns(num_columns)
+                    .spacing([10.0, row_spacing])
+                    .striped(true)
+                    .show(ui, |ui| {
+                        draw_table_header(ui, &state.config);
+                        let mut rows = Vec::new();
+                        for row_idx in 0..state.filtered_items.len() {
+                            let rect = draw_table_row(ui, state, row_idx);
+                            if row_idx == selected_row && selection_moved {
+                                ui.scroll_to_rect(rect, None);
+                            }
+                            rows.push((row_idx, rect));
+                            ui.end_row();
+                        }
+                        if state.mode == Mode::Jump {
+                            draw_jump_labels(ui, state, &rows);
+                        }
+                    });
+            })
+    } else {
+        // Virtual row 0 is the header, so keybind rows start at 1.
+        let total_rows = state.filtered_items.len() + 1;
+        let mut scroll_area = egui::ScrollArea::vertical().auto_shrink([true, false]);
+        if selection_moved {
+            let top = (selected_row + 1) as f32 * row_height_with_spacing;
+            let bottom = top + row_height;
+            let offset = state.table_scroll_offset;
+            if selected_row == 0 {
+                scroll_area = scroll_area.vertical_scroll_offset(0.0);
+            } else if top < offset {
+                scroll_area = scroll_area.vertical_scroll_offset(top);
+            } else if bottom > offset + state.table_viewport_height {
+                scroll_area =
+                    scroll_area.vertical_scroll_offset(bottom - state.table_viewport_height);
+            }
+        }
+
+        scroll_area.show_rows(ui, row_height, total_rows, |ui, row_range| {
+            egui::Grid::new("keybinds_grid")
+                .num_columns(num_columns)
+                .spacing([10.0, row_spacing])
+                .striped(true)
+                .start_row(row_range.start)
+                .show(ui, |ui| {
+                    let mut rows = Vec::new();
+                    for row in row_range {
+                        if row == 0 {
+                            draw_table_header(ui, &state.config);
+                        } else {
+                            rows.push((row - 1, draw_table_row(ui, state, row - 1)));
+                            ui.end_row();
+                        }
+                    }
+                    if state.mode == Mode::Jump {
+                        draw_jump_labels(ui, state, &rows);
+                    }
+                });
+        })
+    };
+
+    if state.dragging_row.is_some() && ui.input(|i| i.pointer.any_released()) {
+        if let (Some(from), Some(to)) = (state.dragging_row.take(), state.drag_target.take()) {
+            state.move_filtered_row(from, to);
+        }
+    }
+
+    state.table_scroll_offset = output.state.offset.y;
+    state.table_viewport_height = output.inner_rect.height();
+    // Remember how many rows fit so paging moves by a screenful.
+    state.visible_rows = ((output.inner_rect.height() / row_height_with_spacing) as usize).max(1);
+}
+
+/// Tells a new user how to get started when the application has no keybinds.
+fn draw_empty_state(ui: &mut Ui, keymap: &Keymap) {
+    ui.centered_and_justified(|ui| {
+        ui.label(
+            RichText::new(format!(
+                "No keybinds yet — press '{}' to add one or ':new <app>' to create an application",
+                key_label(&keymap.new_line_below)
+            ))
+            .weak(),
+        );
+    });
+}
+
+fn draw_table_header(ui: &mut Ui, config: &Config) {
+    if line_numbers_shown(config) {
+        ui.label("");
+    }
+    ui.label("");
+    ui.label(RichText::new("Keybind").strong());
+    ui.label(RichText::new("Description").strong());
+    ui.end_row();
+}
+
+fn line_numbers_shown(config: &Config) -> bool {
+    config.show_line_numbers || config.relative_line_numbers
+}
+
+/// The gutter number of a filtered row. With relative numbers, the selected
+/// row shows its own number if `show_line_numbers` is also set and 0
+/// otherwise, as in vim.
+fn line_number(config: &Config, row_idx: usize, selected_row: usize) -> usize {
+    if !config.relative_line_numbers || (row_idx == selected_row && config.show_line_numbers) {
+        row_idx + 1
+    } else {
+        row_idx.abs_diff(selected_row)
+    }
+}
+
+/// Draws the line number, drag handle and both cells of a filtered row and
+/// returns the rect they cover.
+fn draw_table_row(ui: &mut Ui, state: &mut AppState, row_idx: usize) -> egui::Rect {
+    // --- Line Number ---
+    // Numbers follow the filtered view, so they match `:<n>`.
+    if line_numbers_shown(&state.config) {
+        let width = state.filtered_items.len().to_string().len();
+        let number = line_number(&state.config, row_idx, state.selected_cell.0);
+        ui.label(
+            RichText::new(format!("{:>width$}", number, width = width))
+                .monospace()
+                .weak(),
+        );
+    }
+
+    // --- Drag Handle ---
+    // Rows from other applications (shown by an `app:` search) are labelled.
+    let row_keybind = &state.keybinds[state.filtered_items[row_idx].original_index];
+    let application = &row_keybind.application;
+    let mut handle_text = if *application == state.current_application {
+        "≡".to_string()
+    } else {
+        format!("≡ {}", application)
+    };
+    if row_keybind.starred {
+        handle_text.push_str(" ★");
+    }
+    let handle =
+        ui.add(egui::Label::new(RichText::new(handle_text).weak()).sense(egui::Sense::drag()));
+    if handle.drag_started() && !state.is_locked() {
+        state.dragging_row = Some(row_idx);
+    }
+    if state.dragging_row.is_some() {
+        ui.ctx().set_cursor_icon(egui::CursorIcon::Grabbing);
+    } else if handle.hovered() {
+        ui.ctx().set_cursor_icon(egui::CursorIcon::Grab);
+    }
+
+    let original_index = state.filtered_items[row_idx].original_index;
+    if state.keybinds[original_index].kind == RowKind::Header {
+        let row_rect = handle.rect.union(draw_header_cells(ui, state, row_idx));
+        update_drag_target(ui, state, row_idx, row_rect);
+        draw_modified_marker(ui, state, original_index, row_rect);
+        return row_rect;
+    }
+
+    let item = &state.filtered_items[row_idx];
+    let keybind = &state.keybinds[item.original_index];
+    let keys = display_keys(
+        &keybind.keys,
+        state.config.keybind_notation,
+        state.config.key_display,
+        &state.config.chord_delimiter,
+    );
+    // Newlines would break the fixed row height, so they're only shown as
+    // such when multi-line descriptions are on. `⏎` is one char like `\n`,
+    // so match indices still line up.
+    let description = if state.config.multiline_descriptions {
+        keybind.description.clone()
+    } else {
+        keybind.description.replace('\n', "⏎")
+    };
+    let enabled = keybind.enabled;
+    let match_indices = item.match_indices.clone();
+
+    // --- Keybind Column ---
+    let is_selected = state.selected_cell == (row_idx, 0);
+    let is_editing = is_selected && state.mode == Mode::Insert;
+
+    let keys_response = if is_editing && state.is_listening_for_keybind {
+        let hint = match (&state.captured_keys, state.capturing_sequence) {
+            (Some(captured), true) => format!(
+                "{} — press the next chord, Enter to confirm, Esc to cancel",
+                captured
+            ),
+            (Some(captured), false) => format!(
+                "{} — Enter to confirm, Esc to cancel, press again to replace",
+                captured
+            ),
+            (None, true) => "Press keys...".to_string(),
+            (None, false) => "Press key...".to_string(),
+        };
+        ui.label(RichText::new(hint).monospace())
+    } else if is_editing {
+        let text_edit = egui::TextEdit::singleline(&mut state.temp_edit_buffer)
+            .id(egui::Id::new(CELL_EDIT_ID))
+            .font(state.config.table_font())
+            .margin(vec2(0.0, 0.0))
+            .frame(false);
+        let r = ui.add(text_edit);
+        r.request_focus();
+        r
+    } else {
+        let indices: HashSet<usize> = match_indices
+            .as_ref()
+            .map(|v| v.iter().cloned().collect())
+            .unwrap_or_default();
+        let shortened = truncate_middle(&keys, &indices, state.config.keys_max_width);
+        let (text, indices) = shortened.clone().unwrap_or((keys.to_string(), indices));
+        let mut job = create_highlighted_layout(
+            text,
+            indices,
+            0,
+            state.highlight_enabled,
+            state.config.table_font(),
+            ui,
+        );
+        if !enabled {
+            strike_through(&mut job, ui);
+        }
+        let response = match state.config.keys_align {
+            KeysAlign::Left => ui.label(job),
+            KeysAlign::Right => {
+                ui.with_layout(Layout::right_to_left(egui::Align::Center), |ui| {
+                    ui.label(job)
+                })
+                .inner
+            }
+        };
+        if shortened.is_some() {
+            response.on_hover_text(&keys)
+        } else {
+            response
+        }
+    };
+    if is_selected && state.mode != Mode::Insert {
+        ui.painter().rect_stroke(
+            keys_response.rect.expand(2.0),
+            3.0,
+            ui.visuals().selection.stroke,
+        );
+    }
+
+    // --- Description Column ---
+    let is_selected = state.selected_cell == (row_idx, 1);
+    let is_editing = is_selected && state.mode == Mode::Insert;
+
+    let response = if is_editing {
+        let text_edit = if state.config.multiline_descriptions {
+            egui::TextEdit::multiline(&mut state.temp_edit_buffer)
+                .desired_rows(1)
+                .desired_width(DESCRIPTION_WRAP_WIDTH)
+        } else {
+            egui::TextEdit::singleline(&mut state.temp_edit_buffer)
+        }
+        .id(egui::Id::new(CELL_EDIT_ID))
+        .font(state.config.table_font())
+        .margin(vec2(0.0, 0.0))
+        .frame(false);
+        let r = ui.add(text_edit);
+        r.request_focus();
+        r
+    } else if description.trim().is_empty() {
+        ui.label(
+            RichText::new("⚠ no description")
+                .monospace()
+                .color(ui.visuals().warn_fg_color),
+        )
+    } else {
+        let offset = keys.chars().count() + 1;
+        let indices: HashSet<usize> = match_indices
+            .as_ref()
+            .map(|v| v.iter().cloned().collect())
+            .unwrap_or_default();
+        let mut job = create_highlighted_layout(
+            description.to_string(),
+            indices,
+            offset,
+            state.highlight_enabled,
+            state.config.table_font(),
+            ui,
+        );
+        if state.config.wrap_descriptions {
+            job.wrap.max_width = DESCRIPTION_WRAP_WIDTH;
+        }
+        if !enabled {
+            strike_through(&mut job, ui);
+        }
+        ui.label(job)
+    };
+    if is_selected && state.mode != Mode::Insert {
+        ui.painter().rect_stroke(
+            response.rect.expand(2.0),
+            3.0,
+            ui.visuals().selection.stroke,
+        );
+    }
+
+    let row_rect = handle.rect.union(keys_response.rect).union(response.rect);
+    update_drag_target(ui, state, row_idx, row_rect);
+    draw_modified_marker(ui, state, original_index, row_rect);
+    row_rect
+}
+
+/// Draws a section header's text across both columns and returns the rect it
+/// covers.
+fn draw_header_cells(ui: &mut Ui, state: &mut AppState, row_idx: usize) -> egui::Rect {
+    let is_selected = state.selected_cell.0 == row_idx;
+    if is_selected && state.mode == Mode::Insert {
+        let text_edit = egui::TextEdit::singleline(&mut state.temp_edit_buffer)
+            .id(egui::Id::new(CELL_EDIT_ID))
+            .font(state.config.table_font())
+            .margin(vec2(0.0, 0.0))
+            .frame(false);
+        let r = ui.add(text_edit);
+        r.request_focus();
+        ui.label("");
+        return r.rect;
+    }
+
+    // Both cells stay empty so the header doesn't widen the keys column; the
+    // text is painted over them instead.
+    let keys_cell = ui.label("");
+    ui.label("");
+    let text = &state.keybinds[state.filtered_items[row_idx].original_index].description;
+    let rect = ui.painter().text(
+        keys_cell.rect.left_center(),
+        egui::Align2::LEFT_CENTER,
+        text,
+        state.config.table_font(),
+        ui.visuals().strong_text_color(),
+    );
+    if is_selected {
+        ui.painter()
+            .rect_stroke(rect.expand(2.0), 3.0, ui.visuals().selection.stroke);
+    }
+    rect.union(keys_cell.rect)
+}
+
+/// Sets `drag_target` from the pointer's position over `row_rect` while a row
+/// is being dragged, and marks where it would drop.
+fn update_drag_target(ui: &Ui, state: &mut AppState, row_idx: usize, row_rect: egui::Rect) {
+    if state.dragging_row.is_some() {
+        if let Some(pointer) = ui.ctx().pointer_interact_pos() {
+            let band = row_rect.expand2(vec2(0.0, state.config.table_row_spacing() / 2.0));
+            if band.y_range().contains(pointer.y) {
+                let before = pointer.y < row_rect.center().y;
+                state.drag_target = Some(if before { row_idx } else { row_idx + 1 });
+                let y = if before { band.top() } else { band.bottom() };
+                ui.painter()
+                    .hline(band.x_range(), y, ui.visuals().selection.stroke);
+            }
+        }
+    }
+}
+
+/// Draws a thin bar left of rows changed since they were last loaded or
+/// saved.
+fn draw_modified_marker(ui: &Ui, state: &AppState, index: usize, row_rect: egui::Rect) {
+    if state
+        .modified_this_session
+        .contains(&state.keybinds[index].id)
+    {
+        let x = row_rect.left() - 3.0;
+        ui.painter().vline(
+            x,
+            row_rect.y_range(),
+            egui::Stroke::new(2.0, ui.visuals().warn_fg_color),
+        );
+    }
+}
+
+/// Greys out and strikes through a disabled keybind's text.
+fn strike_through(job: &mut egui::text::LayoutJob, ui: &Ui) {
+    let color = ui.visuals().weak_text_color();
+    for section in &mut job.sections {
+        section.format.color = color;
+        section.format.strikethrough = egui::Stroke::new(1.0, color);
+    }
+}
+
+/// Shortens `text` to `max_chars` characters by replacing its middle with
+/// `…`, moving the highlighted `indices` along. Returns `None` if it already
+/// fits or `max_chars` is 0.
+fn truncate_middle(
+    text: &str,
+    indices: &HashSet<usize>,
+    max_chars: usize,
+) -> Option<(String, HashSet<usize>)> {
+    let chars: Vec<char> = text.chars().collect();
+    if max_chars == 0 || chars.len() <= max_chars {
+        return None;
+    }
+    let kept = max_chars - 1;
+    let head = kept.div_ceil(2);
+    let tail_start = chars.len() - (kept - head);
+
+    let mut shortened: String = chars[..head].iter().collect();
+    shortened.push('…');
+    shortened.extend(&chars[tail_start..]);
+    // Indices in the tail move to after the `…`.
+    let moved = indices
+        .iter()
+        .filter_map(|&i| {
+            if i < head {
+                Some(i)
+            } else if i >= tail_start && i < chars.len() {
+                Some(i - tail_start + head + 1)
+            } else {
+                None
+            }
+        })
+        .collect();
+    Some((shortened, moved))
+}
+
+fn create_highlighted_layout(
+    text: String,
+    indices: HashSet<usize>,
+    offset: usize,
+    highlight: bool,
+    font_id: egui::FontId,
+    ui: &Ui,
+) -> egui::text::LayoutJob {
+    let mut job = egui::text::LayoutJob::default();
+    let theme_visuals = ui.visuals().clone();
+    let highlight_color = Color32::from_rgb(255, 255, 0);
+
+    // The matcher's indices count chars, not bytes.
+    for (i, c) in text.chars().enumerate() {
+        let is_match = highlight && indices.contains(&(i + offset));
+        job.append(
+            &c.to_string(),
+            0.0,
+            TextFormat {
+                font_id: font_id.clone(),
+                color: theme_visuals.text_color(),
+                background: if is_match {
+                    highlight_color
+                } else {
+                    Color32::TRANSPARENT
+                },
+                ..Default::default()
+            },
+        );
+    }
+    job
+}
+
+fn draw_status_bar(ui: &mut Ui, state: &mut AppState) {
+    ui.horizontal(|ui| {
+        let mode_text = if state.leader_key_pressed {
+            "<leader>"
+        } else if state.delete_leader_pressed {
+            "<delete>"
+        } else if state.yank_leader_pressed {
+            "<yank>"
+        } else if state.quit_leader_pressed {
+            "<quit>"
+        } else if state.record_macro_pressed {
+            "<record>"
+        } else if state.play_macro_pressed {
+            "<replay>"
+        } else {
+            match state.mode {
+                Mode::Normal => "-- NORMAL --",
+                Mode::Insert => "-- INSERT --",
+                Mode::Search => "/",
+                Mode::Command => ":",
+                Mode::AppFilter => "Filter Apps:",
+                Mode::Export => "Export:",
+                Mode::Import => "Import:",
+                Mode::Help => "Help:",
+                Mode::History => "History:",
+                Mode::CommandPalette => "Commands:",
+                Mode::ImportMatch => "Import:",
+                Mode::Messages => "Messages:",
+                Mode::Diff => "Diff:",
+                Mode::LoadFailures => "Failed:",
+                Mode::Grep => "Grep:",
+                Mode::QuitConfirm | Mode::ForceQuitConfirm => "Quit:",
+                Mode::Jump => "Jump:",
+            }
+        };
+
+        match state.mode {
+            Mode::Command => {
+                ui.label(RichText::new(":").strong().monospace());
+                let text_edit = ui.add(
+                    egui::TextEdit::singleline(&mut state.command_buffer)
+                        .frame(false)
+                        .desired_width(200.0),
+                );
+                if !text_edit.has_focus() {
+                    text_edit.request_focus();
+                }
+            }
+            Mode::Search => {
+                ui.label(RichText::new("/").strong().monospace());
+                let text_edit = ui.add(
+                    egui::TextEdit::singleline(&mut state.search_query)
+                        .frame(false)
+                        .desired_width(200.0),
+                );
+                if !text_edit.has_focus() {
+                    text_edit.request_focus();
+                }
+            }
+            _ => {
+                ui.label(RichText::new(mode_text).strong().monospace());
+                if !state.leader_key_pressed
+                    && !state.delete_leader_pressed
+                    && !state.yank_leader_pressed
+                    && !state.quit_leader_pressed
+                    && !state.record_macro_pressed
+                    && !state.play_macro_pressed
+                {
+                    if let Some(register) = state.recording_macro {
+                        ui.label(
+                            RichText::new(format!("recording @{}", register))
+                                .monospace()
+                                .color(Severity::Warning.color(ui.visuals())),
+                        );
+                    }
+                    ui.label(
+                        RichText::new(&state.status_message)
+                            .monospace()
+                            .color(state.status_severity.color(ui.visuals())),
+                    );
+                }
+            }
+        }
+
+        ui.with_layout(Layout::right_to_left(egui::Align::Center), |ui| {
+            ui.label(
+                RichText::new(&state.current_application)
+                    .strong()
+                    .monospace()
+                    .color(state.app_color(&state.current_application)),
+            );
+            if state.search_scope != SearchScope::Both {
+                ui.label(
+                    RichText::new(format!("[search {}]", state.search_scope.name()))
+                        .strong()
+                        .monospace()
+                        .color(Color32::YELLOW),
+                );
+            }
+            if state.starred_view {
+                ui.label(
+                    RichText::new("[starred]")
+                        .strong()
+                        .monospace()
+                        .color(Color32::YELLOW),
+                );
+            }
+            if let Some(filter) = state.modifier_filter {
+                ui.label(
+                    RichText::new(format!("[only {}]", filter.name()))
+                        .strong()
+                        .monospace()
+                        .color(Color32::YELLOW),
+                );
+            }
+            if state.locked {
+                ui.label(
+                    RichText::new("[locked]")
+                        .strong()
+                        .monospace()
+                        .color(Color32::LIGHT_RED),
+                );
+            }
+        });
+    });
+}
+
+fn draw_app_filter_popup(ctx: &Context, state: &mut AppState) {
+    let mut close_popup = false;
+    egui::Window::new("Filter by Application")
+        .anchor(egui::Align2::CENTER_CENTER, vec2(0.0, -100.0))
+        .collapsible(false)
+        .resizable(false)
+        .show(ctx, |ui| {
+            if ui.input(|i| i.key_pressed(Key::Escape)) {
+                close_popup = true;
+            }
+            ui.label(
+                "Type to search, ↑/↓ to navigate, Enter (or 1-9 before typing) to select, Space to check, * to pin.",
+            );
+
+            // Space checks apps for export, 1-9 pick one of the first nine and
+            // `*` pins instead of typing into the search. Once something is
+            // typed, digits go into the search so names like `k9s` can be
+            // found.
+            let quick_pick_enabled = state.app_search_query.is_empty();
+            let mut toggle_pin = false;
+            let (toggle_check, quick_pick) = ui.input_mut(|i| {
+                i.events.retain(|e| match e {
+                    egui::Event::Text(text) if text == "*" => {
+                        toggle_pin = true;
+                        false
+                    }
+                    egui::Event::Text(text) => {
+                        text != " "
+                            && !(quick_pick_enabled
+                                && QUICK_PICK_KEYS.iter().any(|(c, _)| text == c))
+                    }
+                    _ => true,
+                });
+                let quick_pick = if quick_pick_enabled {
+                    QUICK_PICK_KEYS
+                        .iter()
+                        .position(|&(_, key)| i.consume_key(Modifiers::NONE, key))
+                } else {
+                    None
+                };
+                (i.consume_key(Modifiers::NONE, Key::Space), quick_pick)
+            });
+
+            let text_edit = ui.add(
+                egui::TextEdit::singleline(&mut state.app_search_query).hint_text("Search..."),
+            );
+            if text_edit.changed() {
+                state.app_filter_selected_index = 0;
+            }
+            if !text_edit.has_focus() {
+                text_edit.request_focus();
+            }
+            ui.separator();
+
+            let all_apps = state.listed_applications();
+            let matcher = SkimMatcherV2::default();
+            // Apps whose name matches come first, then apps with a matching
+            // keybind, shown with the keybind that matched.
+            let (mut filtered_apps, content_matches): (Vec<_>, Vec<_>) = all_apps
+                .into_iter()
+                .filter_map(|app| {
+                    if state.app_search_query.is_empty()
+                        || matcher.fuzzy_match(&app, &state.app_search_query).is_some()
+                    {
+                        return Some((app, None));
+                    }
+                    let hint = state
+                        .keybinds
+                        .iter()
+                        .filter(|kb| kb.application == app)
+                        .map(|kb| format!("{} {}", kb.keys, kb.description))
+                        .find(|text| {
+                            matcher.fuzzy_match(text, &state.app_search_query).is_some()
+                        })?;
+                    Some((app, Some(hint)))
+                })
+                .partition(|(_, hint)| hint.is_none());
+            filtered_apps.extend(content_matches);
+
+            if toggle_pin {
+                if let Some((app, _)) = filtered_apps.get(state.app_filter_selected_index) {
+                    let app = app.clone();
+                    state.toggle_pinned(&app);
+                }
+            }
+            if toggle_check {
+                if let Some((app, _)) = filtered_apps.get(state.app_filter_selected_index) {
+                    if !state.checked_apps.remove(app) {
+                        state.checked_apps.insert(app.clone());
+                    }
+                }
+            }
+
+            egui::ScrollArea::vertical()
+                .max_height(200.0)
+                .show(ui, |ui| {
+                    for (idx, (app, hint)) in filtered_apps.iter().enumerate() {
+                        let is_selected = idx == state.app_filter_selected_index;
+                        ui.horizontal(|ui| {
+                            let number = match QUICK_PICK_KEYS.get(idx) {
+                                Some((c, _)) if quick_pick_enabled => c.to_string(),
+                                _ => " ".to_string(),
+                            };
+                            ui.label(RichText::new(number).monospace().weak());
+                            let mut checked = state.checked_apps.contains(app);
+                            if ui.checkbox(&mut checked, "").changed() {
+                                if checked {
+                                    state.checked_apps.insert(app.clone());
+                                } else {
+                                    state.checked_apps.remove(app);
+                                }
+                            }
+                            let pin = if state.config.pinned_apps.contains(app) {
+                                "★ "
+                            } else {
+                                ""
+                            };
+                            let text = match hint {
+                                Some(hint) => RichText::new(format!(
+                                    "{}{}  — contains \"{}\"",
+                                    pin, app, hint
+                                )),
+                                None => RichText::new(format!("{}{}", pin, app)),
+                            };
+                            let text = if state.app_colors.contains_key(app) {
+                                text.color(state.app_color(app))
+                            } else {
+                                text
+                            };
+                            let label = ui.selectable_label(is_selected, text);
+                            if is_selected {
+                                ui.painter().rect_stroke(
+                                    label.rect,
+                                    3.0,
+                                    ui.visuals().selection.stroke,
+                                );
+                            }
+                            if label.clicked() {
+                                state.switch_to_app(app.clone());
+                                close_popup = true;
+                            }
+                        });
+                    }
+                });
+
+            if !state.checked_apps.is_empty() {
+                ui.separator();
+                if ui
+                    .button(format!(
+                        "Export {} checked app(s)",
+                        state.checked_apps.len()
+                    ))
+                    .clicked()
+                {
+                    if let Some(folder) = rfd::FileDialog::new().pick_folder() {
+                        let mut apps: Vec<_> = state.checked_apps.iter().cloned().collect();
+                        apps.sort();
+                        state.export_apps(&apps, &folder);
+                    }
+                    close_popup = true;
+                }
+            }
+
+            if ui.input(|i| !filtered_apps.is_empty() && i.key_pressed(Key::ArrowDown)) {
+                state.app_filter_selected_index =
+                    (state.app_filter_selected_index + 1).min(filtered_apps.len() - 1);
+            }
+            if ui.input(|i| !filtered_apps.is_empty() && i.key_pressed(Key::ArrowUp)) {
+                state.app_filter_selected_index = state.app_filter_selected_index.saturating_sub(1);
+            }
+            let picked = match quick_pick {
+                Some(idx) if idx < filtered_apps.len() => Some(idx),
+                _ if ui.input(|i| !filtered_apps.is_empty() && i.key_pressed(Key::Enter)) => {
+                    Some(state.app_filter_selected_index)
+                }
+                _ => None,
+            };
+            if let Some(picked) = picked {
+                if let Some((selected_app, _)) = filtered_apps.get(picked) {
+                    state.switch_to_app(selected_app.clone());
+                }
+                close_popup = true;
+            }
+        });
+    if close_popup {
+        state.mode = Mode::Normal;
+        state.app_search_query.clear();
+        state.checked_apps.clear();
+    }
+}
+
+fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Renders a self-contained, printable HTML cheatsheet for one application.
+fn render_html(app: &AppKeybinds) -> String {
+    let title = escape_html(&app.application);
+    let mut html = format!(
+        r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<title>{title} keybinds</title>
+<style>
+  body {{ font-family: sans-serif; margin: 2rem; color: #111; }}
+  h1 {{ border-bottom: 2px solid #111; padding-bottom: 0.25rem; }}
+  h2 {{ font-size: 1.1rem; margin: 1.25rem 0 0.25rem; break-after: avoid; }}
+  .grid {{ display: grid; grid-template-columns: repeat(auto-fill, minmax(16rem, 1fr)); gap: 0.25rem 1.5rem; }}
+  .row {{ display: flex; gap: 0.75rem; padding: 0.2rem 0; border-bottom: 1px solid #ddd; break-inside: avoid; }}
+  .row span {{ white-space: pre-line; }}
+  .notes {{ color: #444; font-style: italic; }}
+  kbd {{ font-family: monospace; font-weight: bold; white-space: nowrap; }}
+  @media print {{ body {{ margin: 0; }} .grid {{ grid-template-columns: repeat(3, 1fr); }} }}
+</style>
+</head>
+<body>
+<h1>{title}</h1>
+"#
+    );
+    if !app.notes.is_empty() {
+        html.push_str(&format!(
+            "<p class=\"notes\">{}</p>\n",
+            escape_html(&app.notes)
+        ));
+    }
+    html.push_str("<div class=\"grid\">\n");
+    for entry in &app.keybinds {
+        if entry.kind == RowKind::Header {
+            html.push_str(&format!(
+                "</div>\n<h2>{}</h2>\n<div class=\"grid\">\n",
+                escape_html(&entry.description)
+            ));
+            continue;
+        }
+        html.push_str(&format!(
+            "  <div class=\"row\"><kbd>{}</kbd><span>{}</span></div>\n",
+            escape_html(&entry.keys),
+            escape_html(&entry.description)
+        ));
+    }
+    html.push_str("</div>\n</body>\n</html>\n");
+    html
+}
+
+/// Lists each distinct, non-empty keys string once per line, in table order.
+fn render_keys_list(app: &AppKeybinds) -> String {
+    let mut seen = HashSet::new();
+    let mut list = String::new();
+    for entry in &app.keybinds {
+        let keys = entry.keys.trim();
+        if !keys.is_empty() && seen.insert(keys) {
+            list.push_str(keys);
+            list.push('\n');
+        }
+    }
+    list
+}
+
+fn draw_export_popup(ctx: &Context, state: &mut AppState) {
+    let mut close_popup = false;
+    egui::Window::new("Export Keybinds")
+        .anchor(egui::Align2::CENTER_CENTER, vec2(0.0, -100.0))
+        .collapsible(false)
+        .resizable(false)
+        .show(ctx, |ui| {
+            if ui.input(|i| i.key_pressed(Key::Escape)) {
+                close_popup = true;
+            }
+            ui.label("Choose what to export:");
+            ui.separator();
+
+            if ui
+                .button(format!("Export '{}' only", state.current_application))
+                .clicked()
+            {
+                let app_keybinds = state.app_keybinds(&state.current_application);
+                if let Ok(json) = state.export_json(app_keybinds) {
+                    if let Some(path) = rfd::FileDialog::new()
+                        .add_filter("json", &["json"])
+                        .set_file_name(app_file_name(&state.current_application))
+                        .save_file()
+                    {
+                        if fs::write(path, json).is_ok() {
+                            state.set_status(Severity::Success, "Export successful.");
+                        } else {
+                            state.set_status(Severity::Error, "Error: Failed to write to file.");
+                        }
+                    }
+                }
+                close_popup = true;
+            }
+
+            let is_filtered = !state.search_query.is_empty() || state.modifier_filter.is_some();
+            if ui
+                .add_enabled(
+                    is_filtered,
+                    egui::Button::new(format!(
+                        "Export filtered results ({})",
+                        state.filtered_items.len()
+                    )),
+                )
+                .on_disabled_hover_text("Search or use :only to filter the table first.")
+                .clicked()
+            {
+                let app_keybinds = state.filtered_app_keybinds();
+                if let Ok(json) = state.export_json(app_keybinds) {
+                    if let Some(path) = rfd::FileDialog::new()
+                        .add_filter("json", &["json"])
+                        .set_file_name(format!("{}-filtered.json", state.current_application))
+                        .save_file()
+                    {
+                        if fs::write(path, json).is_ok() {
+                            state.set_status(Severity::Success, "Export successful.");
+                        } else {
+                            state.set_status(Severity::Error, "Error: Failed to write to file.");
+                        }
+                    }
+                }
+                close_popup = true;
+            }
+
+            if ui.button("Export as HTML").clicked() {
+                let html = render_html(&state.app_keybinds(&state.current_application));
+                if let Some(path) = rfd::FileDialog::new()
+                    .add_filter("html", &["html"])
+                    .set_file_name(format!("{}.html", state.current_application))
+                    .save_file()
+                {
+                    if fs::write(path, html).is_ok() {
+                        state.set_status(Severity::Success, "Export successful.");
+                    } else {
+                        state.set_status(Severity::Error, "Error: Failed to write to file.");
+                    }
+                }
+                close_popup = true;
+            }
+
+            if ui.button("Export keys list").clicked() {
+                let keys_list = render_keys_list(&state.app_keybinds(&state.current_application));
+                if let Some(path) = rfd::FileDialog::new()
+                    .add_filter("txt", &["txt"])
+                    .set_file_name(format!("{}-keys.txt", state.current_application))
+                    .save_file()
+                {
+                    if fs::write(path, keys_list).is_ok() {
+                        state.set_status(Severity::Success, "Export successful.");
+                    } else {
+                        state.set_status(Severity::Error, "Error: Failed to write to file.");
+                    }
+                }
+                close_popup = true;
+            }
+
+            if ui.button("Export All").clicked() {
+                if let Some(folder) = rfd::FileDialog::new().pick_folder() {
+                    state.export_apps(&state.get_all_applications(), &folder);
+                }
+                close_popup = true;
+            }
+
+            #[cfg(feature = "share")]
+            if ui
+                .button(format!("Share '{}' as link", state.current_application))
+                .on_hover_text(format!(
+                    "Uploads the JSON to {}",
+                    state.config.share_endpoint
+                ))
+                .clicked()
+            {
+                state.share_current_app();
+                close_popup = true;
+            }
+        });
+    if close_popup {
+        state.mode = Mode::Normal;
+    }
+}
+
+/// Splits a tmux config line into its code and an optional trailing comment.
+/// A `#` only starts a comment outside quotes and when it isn't a `#{format}`.
+fn split_tmux_comment(line: &str) -> (&str, Option<String>) {
+    let mut quote = None;
+    let mut prev = ' ';
+    for (i, c) in line.char_indices() {
+        match quote {
+            Some(q) if c == q => quote = None,
+            Some(_) => {}
+            None if c == '"' || c == '\'' => quote = Some(c),
+            None if c == '#' && prev.is_whitespace() && !line[i..].starts_with("#{") => {
+                let comment = line[i + 1..].trim();
+                let comment = (!comment.is_empty()).then(|| comment.to_string());
+                return (line[..i].trim_end(), comment);
+            }
+            None => {}
+        }
+        prev = c;
+    }
+    (line, None)
+}
+
+/// Splits tmux command arguments on whitespace, honouring quotes and escapes.
+fn split_tmux_words(code: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut current = String::new();
+    let mut in_word = false;
+    let mut quote = None;
+    let mut chars = code.chars();
+    while let Some(c) = chars.next() {
+        match quote {
+            Some(q) if c == q => quote = None,
+            Some('"') if c == '\\' => current.extend(chars.next()),
+            Some(_) => current.push(c),
+            None if c == '"' || c == '\'' => {
+                quote = Some(c);
+                in_word = true;
+            }
+            None if c == '\\' => {
+                current.extend(chars.next());
+                in_word = true;
+            }
+            None if c.is_whitespace() => {
+                if in_word {
+                    words.push(std::mem::take(&mut current));
+                    in_word = false;
+                }
+            }
+            None => {
+                current.push(c);
+                in_word = true;
+            }
+        }
     }
+    if in_word {
+        words.push(current);
+    }
+    words
 }
 
-fn handle_command_mode_input(ctx: &Context, state: &mut AppState) {
-    ctx.input(|i| {
-        if i.key_pressed(Key::Escape) {
-            state.mode = Mode::Normal;
-            state.command_buffer.clear();
+/// Converts tmux modifier prefixes (`C-`, `M-`, `S-`) to ctrlset's notation.
+fn tmux_key(key: &str) -> String {
+    let mut parts = Vec::new();
+    let mut rest = key;
+    while rest.len() > 2 && rest.as_bytes()[1] == b'-' {
+        let modifier = match rest.as_bytes()[0] {
+            b'C' => "Ctrl",
+            b'M' => "Alt",
+            b'S' => "Shift",
+            _ => break,
+        };
+        parts.push(modifier.to_string());
+        rest = &rest[2..];
+    }
+    parts.push(rest.to_string());
+    parts.join("+")
+}
+
+/// Parses `bind`/`bind-key` lines from a `.tmux.conf` into keybinds for the
+/// "tmux" application. The description is taken from `-N`, then a trailing
+/// comment, and finally the bound command itself.
+fn parse_tmux_conf(data: &str) -> AppKeybinds {
+    let mut keybinds = Vec::new();
+    for line in data.lines() {
+        let (code, comment) = split_tmux_comment(line.trim());
+        let words = split_tmux_words(code);
+        let mut words = words.iter().map(String::as_str);
+        if !matches!(words.next(), Some("bind") | Some("bind-key")) {
+            continue;
         }
-        if i.key_pressed(Key::Enter) {
-            let parts: Vec<&str> = state.command_buffer.split_whitespace().collect();
-            let mut command_finished = true;
-            match parts.as_slice() {
-                ["w"] => state.save_current_app_keybinds(),
-                ["wq"] => {
-                    state.save_current_app_keybinds();
-                    state.should_quit = true;
-                }
-                ["q"] => {
-                    if state.dirty {
-                        state.status_message =
-                            "Unsaved changes! Use :q! to force quit.".to_string();
-                    } else {
-                        state.should_quit = true;
+
+        let mut table = "prefix";
+        let mut note = None;
+        let mut key = None;
+        while let Some(word) = words.next() {
+            if word.len() > 1 && word.starts_with('-') {
+                for flag in word[1..].chars() {
+                    match flag {
+                        'n' => table = "root",
+                        'T' => table = words.next().unwrap_or(table),
+                        'N' => note = words.next(),
+                        _ => {}
                     }
                 }
-                ["q!"] => state.should_quit = true,
-                ["help"] => {
-                    state.mode = Mode::Help;
-                    command_finished = false;
-                }
-                ["new", app_name @ ..] => {
-                    let app_name_str = app_name.join(" ");
-                    if !app_name_str.is_empty() && !state.all_applications.contains(&app_name_str) {
-                        state.all_applications.insert(app_name_str.clone());
-                        state.current_application = app_name_str;
-                        state.refilter();
-                        state.dirty = true;
-                        state.status_message =
-                            format!("Created new app '{}'.", state.current_application);
-                    } else {
-                        state.status_message = "App name invalid or already exists.".to_string();
+            } else {
+                key = Some(word);
+                break;
+            }
+        }
+        let Some(key) = key else {
+            continue;
+        };
+
+        let command = words.collect::<Vec<_>>().join(" ");
+        let keys = match table {
+            "root" => tmux_key(key),
+            "prefix" => format!("Prefix {}", tmux_key(key)),
+            other => format!("[{}] {}", other, tmux_key(key)),
+        };
+        let description = note.map(str::to_string).or(comment).unwrap_or(command);
+        keybinds.push(KeybindEntry {
+            keys,
+            description,
+            uses: 0,
+            kind: RowKind::Keybind,
+            enabled: true,
+            starred: false,
+            id: 0,
+        });
+    }
+
+    AppKeybinds {
+        application: "tmux".to_string(),
+        notes: String::new(),
+        color: None,
+        keybinds,
+    }
+}
+
+#[derive(Deserialize)]
+struct VsCodeKeybinding {
+    key: String,
+    command: String,
+    #[serde(default)]
+    when: Option<String>,
+}
+
+/// Parses a VS Code `keybindings.json` into keybinds for the "vscode"
+/// application. Whole-line `//` comments are stripped first, and entries that
+/// remove a default binding (`-command`) are skipped.
+fn parse_vscode_keybindings(data: &str) -> Result<AppKeybinds, String> {
+    let json: String = data
+        .lines()
+        .filter(|line| !line.trim_start().starts_with("//"))
+        .collect::<Vec<_>>()
+        .join("\n");
+    let bindings: Vec<VsCodeKeybinding> =
+        serde_json::from_str(&json).map_err(|e| format!("Error: Failed to parse JSON: {}", e))?;
+
+    let keybinds = bindings
+        .into_iter()
+        .filter(|b| !b.command.starts_with('-'))
+        .map(|b| KeybindEntry {
+            keys: b.key,
+            description: match b.when {
+                Some(when) => format!("{} (when: {})", b.command, when),
+                None => b.command,
+            },
+            uses: 0,
+            kind: RowKind::Keybind,
+            enabled: true,
+            starred: false,
+            id: 0,
+        })
+        .collect();
+
+    Ok(AppKeybinds {
+        application: "vscode".to_string(),
+        notes: String::new(),
+        color: None,
+        keybinds,
+    })
+}
+
+fn draw_import_popup(ctx: &Context, state: &mut AppState) {
+    let mut close_popup = false;
+    egui::Window::new("Import Keybinds")
+        .anchor(egui::Align2::CENTER_CENTER, vec2(0.0, -100.0))
+        .collapsible(false)
+        .resizable(false)
+        .show(ctx, |ui| {
+            if ui.input(|i| i.key_pressed(Key::Escape)) {
+                close_popup = true;
+            }
+            ui.label("Select a file to import.");
+            ui.separator();
+
+            let import_logic = |replace: bool, state: &mut AppState| {
+                if let Some(path) = rfd::FileDialog::new()
+                    .add_filter("json", &["json"])
+                    .pick_file()
+                {
+                    if let Ok(data) = fs::read_to_string(path) {
+                        if let Ok(imported_app) = serde_json::from_str::<AppKeybinds>(&data) {
+                            state.import_app_keybinds(imported_app, replace);
+                        } else {
+                            state.set_status(Severity::Error, "Error: Failed to parse JSON file.");
+                        }
+                    }
+                }
+            };
+
+            if ui.button("Import and Merge").clicked() {
+                import_logic(false, state);
+                close_popup = true;
+            }
+            if ui.button("Import and Replace").clicked() {
+                import_logic(true, state);
+                close_popup = true;
+            }
+            if ui.button("Import Folder").clicked() {
+                if let Some(folder) = rfd::FileDialog::new().pick_folder() {
+                    state.import_folder(&folder);
+                }
+                close_popup = true;
+            }
+            if ui.button("Import tmux conf").clicked() {
+                if let Some(path) = rfd::FileDialog::new().pick_file() {
+                    match fs::read_to_string(&path) {
+                        Ok(data) => state.import_app_keybinds(parse_tmux_conf(&data), false),
+                        Err(_) => {
+                            state.set_status(
+                                Severity::Error,
+                                format!("Error: Failed to read {}.", path.display()),
+                            );
+                        }
+                    }
+                }
+                close_popup = true;
+            }
+            if ui.button("Import VS Code keybindings").clicked() {
+                if let Some(path) = rfd::FileDialog::new()
+                    .add_filter("json", &["json"])
+                    .pick_file()
+                {
+                    match fs::read_to_string(&path) {
+                        Ok(data) => match parse_vscode_keybindings(&data) {
+                            Ok(app) => state.import_app_keybinds(app, false),
+                            Err(e) => state.set_status(Severity::Error, e),
+                        },
+                        Err(_) => {
+                            state.set_status(
+                                Severity::Error,
+                                format!("Error: Failed to read {}.", path.display()),
+                            );
+                        }
                     }
                 }
-                _ => state.status_message = format!("Not a command: {}", state.command_buffer),
+                close_popup = true;
             }
+        });
+    // An import may have switched to asking which application to merge into.
+    if close_popup && state.mode == Mode::Import {
+        state.mode = Mode::Normal;
+    }
+}
 
-            if command_finished {
-                state.mode = Mode::Normal;
+/// Asks which existing application a pending import should be merged into
+/// when its name nearly matches one, or whether to keep it separate.
+fn draw_import_match_popup(ctx: &Context, state: &mut AppState) {
+    let Some((imported_app, _)) = &state.pending_import else {
+        state.mode = Mode::Normal;
+        return;
+    };
+    let imported_name = imported_app.application.clone();
+    let mut choices: Vec<(String, String)> = state
+        .near_matching_apps(&imported_name)
+        .into_iter()
+        .map(|app| (format!("Merge into '{}'", app), app))
+        .collect();
+    choices.push((
+        format!("Keep '{}' as a new application", imported_name),
+        imported_name.clone(),
+    ));
+
+    let mut close_popup = false;
+    let mut chosen = None;
+    egui::Window::new("Similar Application Found")
+        .anchor(egui::Align2::CENTER_CENTER, vec2(0.0, -100.0))
+        .collapsible(false)
+        .resizable(false)
+        .show(ctx, |ui| {
+            if ui.input(|i| i.key_pressed(Key::Escape)) {
+                close_popup = true;
             }
-            state.command_buffer.clear();
+            ui.label(format!(
+                "'{}' is similar to an existing application. ↑/↓ to choose, Enter to import.",
+                imported_name
+            ));
+            ui.separator();
+
+            if ui.input(|i| i.key_pressed(Key::ArrowDown)) {
+                state.import_match_index = (state.import_match_index + 1).min(choices.len() - 1);
+            }
+            if ui.input(|i| i.key_pressed(Key::ArrowUp)) {
+                state.import_match_index = state.import_match_index.saturating_sub(1);
+            }
+
+            for (idx, (label, app)) in choices.iter().enumerate() {
+                let is_selected = idx == state.import_match_index;
+                let response = ui.selectable_label(is_selected, label);
+                if is_selected {
+                    ui.painter()
+                        .rect_stroke(response.rect, 3.0, ui.visuals().selection.stroke);
+                }
+                if response.clicked() {
+                    chosen = Some(app.clone());
+                }
+            }
+
+            if ui.input(|i| i.key_pressed(Key::Enter)) {
+                chosen = choices
+                    .get(state.import_match_index)
+                    .map(|(_, app)| app.clone());
+            }
+        });
+
+    if let Some(app) = chosen {
+        if let Some((mut imported_app, replace)) = state.pending_import.take() {
+            imported_app.application = app;
+            state.merge_app_keybinds(imported_app, replace);
         }
-    });
+        close_popup = true;
+    } else if close_popup {
+        state.pending_import = None;
+        state.set_status(Severity::Info, "Import cancelled.");
+    }
+    if close_popup {
+        state.mode = Mode::Normal;
+    }
 }
 
-fn draw_main_table(ui: &mut Ui, state: &mut AppState) {
-    egui::Grid::new("keybinds_grid")
-        .num_columns(2)
-        .spacing([10.0, 4.0])
-        .striped(true)
-        .show(ui, |ui| {
-            ui.label(RichText::new("Keybind").strong());
-            ui.label(RichText::new("Description").strong());
-            ui.end_row();
-
-            ui.add_sized([ui.available_width(), 0.0], egui::Label::new(""));
-            ui.end_row();
-
-            let items = state
-                .filtered_items
+fn draw_command_palette(ctx: &Context, state: &mut AppState) {
+    let mut close_popup = false;
+    let mut command_to_run = None;
+    egui::Window::new("Command Palette")
+        .anchor(egui::Align2::CENTER_CENTER, vec2(0.0, -100.0))
+        .collapsible(false)
+        .resizable(false)
+        .show(ctx, |ui| {
+            if ui.input(|i| i.key_pressed(Key::Escape)) {
+                close_popup = true;
+            }
+            ui.label("Type to search, ↑/↓ to navigate, Enter to run.");
+
+            let text_edit =
+                ui.add(egui::TextEdit::singleline(&mut state.palette_query).hint_text("Search..."));
+            if text_edit.changed() {
+                state.palette_selected_index = 0;
+            }
+            if !text_edit.has_focus() {
+                text_edit.request_focus();
+            }
+            ui.separator();
+
+            let matcher = SkimMatcherV2::default();
+            let filtered_commands: Vec<&(&str, &str)> = COMMANDS
                 .iter()
-                .enumerate()
-                .map(|(i, item)| {
-                    let keybind = &state.keybinds[item.original_index];
-                    (
-                        i,
-                        keybind.keys.clone(),
-                        keybind.description.clone(),
-                        item.match_indices.clone(),
-                    )
+                .filter(|(name, description)| {
+                    state.palette_query.is_empty()
+                        || matcher
+                            .fuzzy_match(&format!("{} {}", name, description), &state.palette_query)
+                            .is_some()
                 })
-                .collect::<Vec<_>>();
-
-            for (row_idx, keys, description, match_indices) in items {
-                // --- Keybind Column ---
-                let is_selected = state.selected_cell == (row_idx, 0);
-                let is_editing = is_selected && state.mode == Mode::Insert;
+                .collect();
 
-                let response = if is_editing && state.is_listening_for_keybind {
-                    ui.label(RichText::new("Press key...").monospace())
-                } else {
-                    let indices: HashSet<usize> = match_indices
-                        .as_ref()
-                        .map(|v| v.iter().cloned().collect())
-                        .unwrap_or_default();
-                    let job = create_highlighted_layout(keys.to_string(), indices, 0, ui);
-                    ui.label(job)
-                };
-                if is_selected && state.mode != Mode::Insert {
-                    ui.painter().rect_stroke(
-                        response.rect.expand(2.0),
-                        3.0,
-                        ui.visuals().selection.stroke,
-                    );
-                }
+            // Handle navigation before drawing so the list can follow the selection.
+            let mut selection_moved = false;
+            if ui.input(|i| !filtered_commands.is_empty() && i.key_pressed(Key::ArrowDown)) {
+                state.palette_selected_index =
+                    (state.palette_selected_index + 1).min(filtered_commands.len() - 1);
+                selection_moved = true;
+            }
+            if ui.input(|i| !filtered_commands.is_empty() && i.key_pressed(Key::ArrowUp)) {
+                state.palette_selected_index = state.palette_selected_index.saturating_sub(1);
+                selection_moved = true;
+            }
 
-                // --- Description Column ---
-                let is_selected = state.selected_cell == (row_idx, 1);
-                let is_editing = is_selected && state.mode == Mode::Insert;
+            egui::ScrollArea::vertical()
+                .max_height(300.0)
+                .show(ui, |ui| {
+                    egui::Grid::new("palette_grid")
+                        .num_columns(2)
+                        .spacing([20.0, 4.0])
+                        .show(ui, |ui| {
+                            for (idx, (name, description)) in filtered_commands.iter().enumerate() {
+                                let is_selected = idx == state.palette_selected_index;
+                                let label = ui.selectable_label(
+                                    is_selected,
+                                    RichText::new(format!(":{}", name)).monospace(),
+                                );
+                                if is_selected && selection_moved {
+                                    label.scroll_to_me(None);
+                                }
+                                if is_selected {
+                                    ui.painter().rect_stroke(
+                                        label.rect,
+                                        3.0,
+                                        ui.visuals().selection.stroke,
+                                    );
+                                }
+                                if label.clicked() {
+                                    command_to_run = Some(*name);
+                                }
+                                ui.label(*description);
+                                ui.end_row();
+                            }
+                        });
+                });
 
-                let response = if is_editing {
-                    let text_edit = egui::TextEdit::singleline(&mut state.temp_edit_buffer)
-                        .font(egui::FontId::monospace(14.0))
-                        .frame(false);
-                    let r = ui.add(text_edit);
-                    r.request_focus();
-                    r
-                } else {
-                    let offset = keys.len() + 1;
-                    let indices: HashSet<usize> = match_indices
-                        .as_ref()
-                        .map(|v| v.iter().cloned().collect())
-                        .unwrap_or_default();
-                    let job =
-                        create_highlighted_layout(description.to_string(), indices, offset, ui);
-                    ui.label(job)
-                };
-                if is_selected && state.mode != Mode::Insert {
-                    ui.painter().rect_stroke(
-                        response.rect.expand(2.0),
-                        3.0,
-                        ui.visuals().selection.stroke,
-                    );
+            if ui.input_mut(|i| i.consume_key(Modifiers::NONE, Key::Enter)) {
+                if let Some((name, _)) = filtered_commands.get(state.palette_selected_index) {
+                    command_to_run = Some(*name);
                 }
-
-                ui.end_row();
             }
         });
-}
-
-fn create_highlighted_layout(
-    text: String,
-    indices: HashSet<usize>,
-    offset: usize,
-    ui: &Ui,
-) -> egui::text::LayoutJob {
-    let mut job = egui::text::LayoutJob::default();
-    let theme_visuals = ui.visuals().clone();
-    let highlight_color = Color32::from_rgb(255, 255, 0);
 
-    for (i, c) in text.char_indices() {
-        let is_match = indices.contains(&(i + offset));
-        job.append(
-            &c.to_string(),
-            0.0,
-            TextFormat {
-                font_id: egui::FontId::monospace(14.0),
-                color: theme_visuals.text_color(),
-                background: if is_match {
-                    highlight_color
-                } else {
-                    Color32::TRANSPARENT
-                },
-                ..Default::default()
-            },
-        );
+    if close_popup || command_to_run.is_some() {
+        state.mode = Mode::Normal;
+        state.palette_query.clear();
+    }
+    if let Some(name) = command_to_run {
+        // Commands that take an argument drop into Command mode for it.
+        match name.split_once('<') {
+            Some((prefix, _)) => {
+                state.command_buffer = prefix.to_string();
+                state.mode = Mode::Command;
+            }
+            None => state.run_command(name),
+        }
     }
-    job
 }
 
-fn draw_status_bar(ui: &mut Ui, state: &mut AppState) {
-    ui.horizontal(|ui| {
-        let mode_text = if state.leader_key_pressed {
-            "<leader>"
-        } else if state.delete_leader_pressed {
-            "<delete>"
-        } else {
-            match state.mode {
-                Mode::Normal => "-- NORMAL --",
-                Mode::Insert => "-- INSERT --",
-                Mode::Search => "/",
-                Mode::Command => ":",
-                Mode::AppFilter => "Filter Apps:",
-                Mode::Export => "Export:",
-                Mode::Import => "Import:",
-                Mode::Help => "Help:",
+fn draw_history_popup(ctx: &Context, state: &mut AppState) {
+    let mut close_popup = false;
+    egui::Window::new("Undo History")
+        .anchor(egui::Align2::CENTER_CENTER, vec2(0.0, -100.0))
+        .collapsible(false)
+        .resizable(false)
+        .show(ctx, |ui| {
+            if ui.input(|i| i.key_pressed(Key::Escape)) {
+                close_popup = true;
             }
-        };
+            ui.label("j/k to navigate, Enter to restore the state before a change.");
+            ui.separator();
 
-        match state.mode {
-            Mode::Command => {
-                ui.label(RichText::new(":").strong().monospace());
-                let text_edit = ui.add(
-                    egui::TextEdit::singleline(&mut state.command_buffer)
-                        .frame(false)
-                        .desired_width(200.0),
-                );
-                if !text_edit.has_focus() {
-                    text_edit.request_focus();
-                }
+            if state.undo_history.is_empty() {
+                ui.label("No history yet.");
+                return;
             }
-            Mode::Search => {
-                ui.label(RichText::new("/").strong().monospace());
-                let text_edit = ui.add(
-                    egui::TextEdit::singleline(&mut state.search_query)
-                        .frame(false)
-                        .desired_width(200.0),
-                );
-                if !text_edit.has_focus() {
-                    text_edit.request_focus();
-                }
+
+            // Newest snapshots are listed first.
+            let count = state.undo_history.len();
+            let rows: Vec<(String, String, (usize, usize))> = state
+                .undo_history
+                .iter()
+                .rev()
+                .map(|entry| {
+                    (
+                        format_elapsed(entry.timestamp),
+                        entry.description.clone(),
+                        diff_counts(&entry.keybinds, &state.keybinds),
+                    )
+                })
+                .collect();
+
+            egui::ScrollArea::vertical()
+                .max_height(300.0)
+                .show(ui, |ui| {
+                    egui::Grid::new("history_grid")
+                        .num_columns(3)
+                        .spacing([20.0, 4.0])
+                        .show(ui, |ui| {
+                            for (idx, (elapsed, description, (added, removed))) in
+                                rows.iter().enumerate()
+                            {
+                                let is_selected = idx == state.history_selected_index;
+                                let label = ui.selectable_label(
+                                    is_selected,
+                                    RichText::new(elapsed).monospace(),
+                                );
+                                if is_selected {
+                                    ui.painter().rect_stroke(
+                                        label.rect,
+                                        3.0,
+                                        ui.visuals().selection.stroke,
+                                    );
+                                }
+                                if label.clicked() {
+                                    state.history_selected_index = idx;
+                                }
+                                ui.label(description);
+                                ui.label(
+                                    RichText::new(format!("+{} -{} keybinds", added, removed))
+                                        .monospace(),
+                                );
+                                ui.end_row();
+                            }
+                        });
+                });
+
+            if ui.input(|i| i.key_pressed(Key::J) || i.key_pressed(Key::ArrowDown)) {
+                state.history_selected_index = (state.history_selected_index + 1).min(count - 1);
             }
-            _ => {
-                ui.label(RichText::new(mode_text).strong().monospace());
-                if !state.leader_key_pressed && !state.delete_leader_pressed {
-                    ui.label(RichText::new(&state.status_message).monospace());
+            if ui.input(|i| i.key_pressed(Key::K) || i.key_pressed(Key::ArrowUp)) {
+                state.history_selected_index = state.history_selected_index.saturating_sub(1);
+            }
+            if ui.input(|i| i.key_pressed(Key::Enter)) {
+                if !state.is_locked() {
+                    state.jump_to_history(count - 1 - state.history_selected_index);
                 }
+                close_popup = true;
             }
-        }
-
-        ui.with_layout(Layout::right_to_left(egui::Align::Center), |ui| {
-            ui.label(
-                RichText::new(&state.current_application)
-                    .strong()
-                    .monospace()
-                    .color(Color32::LIGHT_BLUE),
-            );
         });
-    });
+    if close_popup {
+        state.mode = Mode::Normal;
+    }
 }
 
-fn draw_app_filter_popup(ctx: &Context, state: &mut AppState) {
+fn draw_grep_popup(ctx: &Context, state: &mut AppState) {
     let mut close_popup = false;
-    egui::Window::new("Filter by Application")
+    let mut selected = None;
+    egui::Window::new(format!("Matches for '{}'", state.grep_pattern))
         .anchor(egui::Align2::CENTER_CENTER, vec2(0.0, -100.0))
         .collapsible(false)
         .resizable(false)
@@ -1179,155 +7009,221 @@ fn draw_app_filter_popup(ctx: &Context, state: &mut AppState) {
             if ui.input(|i| i.key_pressed(Key::Escape)) {
                 close_popup = true;
             }
-            ui.label("Type to search, ↑/↓ to navigate, Enter to select.");
+            let count = state.grep_results.len();
+            ui.label(format!(
+                "{} match(es). j/k to navigate, Enter to go to the keybind.",
+                count
+            ));
+            ui.separator();
 
-            let text_edit = ui.add(
-                egui::TextEdit::singleline(&mut state.app_search_query).hint_text("Search..."),
-            );
-            if text_edit.changed() {
-                state.app_filter_selected_index = 0;
+            // Handle navigation before drawing so the list can follow the selection.
+            let mut selection_moved = false;
+            if ui.input(|i| i.key_pressed(Key::J) || i.key_pressed(Key::ArrowDown)) {
+                state.grep_selected_index = (state.grep_selected_index + 1).min(count - 1);
+                selection_moved = true;
             }
-            if !text_edit.has_focus() {
-                text_edit.request_focus();
+            if ui.input(|i| i.key_pressed(Key::K) || i.key_pressed(Key::ArrowUp)) {
+                state.grep_selected_index = state.grep_selected_index.saturating_sub(1);
+                selection_moved = true;
             }
-            ui.separator();
-
-            let all_apps = state.get_all_applications();
-            let matcher = SkimMatcherV2::default();
-            let filtered_apps: Vec<String> = all_apps
-                .into_iter()
-                .filter(|app| {
-                    matcher.fuzzy_match(app, &state.app_search_query).is_some()
-                        || state.app_search_query.is_empty()
-                })
-                .collect();
 
             egui::ScrollArea::vertical()
-                .max_height(200.0)
+                .max_height(300.0)
                 .show(ui, |ui| {
-                    for (idx, app) in filtered_apps.iter().enumerate() {
-                        let is_selected = idx == state.app_filter_selected_index;
-                        let label = ui.selectable_label(is_selected, app);
-                        if is_selected {
-                            ui.painter().rect_stroke(
-                                label.rect,
-                                3.0,
-                                ui.visuals().selection.stroke,
-                            );
-                        }
-                        if label.clicked() {
-                            state.current_application = app.clone();
-                            close_popup = true;
-                            state.refilter();
-                        }
-                    }
+                    egui::Grid::new("grep_grid")
+                        .num_columns(3)
+                        .spacing([20.0, 4.0])
+                        .show(ui, |ui| {
+                            for (idx, &kb_index) in state.grep_results.iter().enumerate() {
+                                let kb = &state.keybinds[kb_index];
+                                let is_selected = idx == state.grep_selected_index;
+                                let label = ui.selectable_label(
+                                    is_selected,
+                                    RichText::new(&kb.application)
+                                        .color(state.app_color(&kb.application)),
+                                );
+                                if is_selected && selection_moved {
+                                    label.scroll_to_me(None);
+                                }
+                                if is_selected {
+                                    ui.painter().rect_stroke(
+                                        label.rect,
+                                        3.0,
+                                        ui.visuals().selection.stroke,
+                                    );
+                                }
+                                if label.clicked() {
+                                    selected = Some(kb_index);
+                                }
+                                ui.label(
+                                    RichText::new(display_keys(
+                                        &kb.keys,
+                                        state.config.keybind_notation,
+                                        state.config.key_display,
+                                        &state.config.chord_delimiter,
+                                    ))
+                                    .monospace(),
+                                );
+                                ui.label(&kb.description);
+                                ui.end_row();
+                            }
+                        });
                 });
 
-            if ui.input(|i| !filtered_apps.is_empty() && i.key_pressed(Key::ArrowDown)) {
-                state.app_filter_selected_index =
-                    (state.app_filter_selected_index + 1).min(filtered_apps.len() - 1);
-            }
-            if ui.input(|i| !filtered_apps.is_empty() && i.key_pressed(Key::ArrowUp)) {
-                state.app_filter_selected_index = state.app_filter_selected_index.saturating_sub(1);
-            }
-            if ui.input(|i| !filtered_apps.is_empty() && i.key_pressed(Key::Enter)) {
-                if let Some(selected_app) = filtered_apps.get(state.app_filter_selected_index) {
-                    state.current_application = selected_app.clone();
-                    state.refilter();
-                }
-                close_popup = true;
+            if ui.input(|i| i.key_pressed(Key::Enter)) {
+                selected = state.grep_results.get(state.grep_selected_index).copied();
             }
         });
+    if let Some(index) = selected {
+        state.select_keybind(index);
+        close_popup = true;
+    }
     if close_popup {
         state.mode = Mode::Normal;
-        state.app_search_query.clear();
     }
 }
 
-fn draw_export_popup(ctx: &Context, state: &mut AppState) {
+fn draw_messages_popup(ctx: &Context, state: &mut AppState) {
     let mut close_popup = false;
-    egui::Window::new("Export Keybinds")
+    egui::Window::new("Messages")
         .anchor(egui::Align2::CENTER_CENTER, vec2(0.0, -100.0))
         .collapsible(false)
         .resizable(false)
         .show(ctx, |ui| {
-            if ui.input(|i| i.key_pressed(Key::Escape)) {
+            if ui.input(|i| i.key_pressed(Key::Escape) || i.key_pressed(Key::Enter)) {
                 close_popup = true;
             }
-            ui.label("Choose what to export:");
-            ui.separator();
 
-            if ui
-                .button(format!("Export '{}' only", state.current_application))
-                .clicked()
-            {
-                let entries: Vec<_> = state
-                    .keybinds
-                    .iter()
-                    .filter(|kb| kb.application == state.current_application)
-                    .map(|kb| KeybindEntry {
-                        keys: kb.keys.clone(),
-                        description: kb.description.clone(),
-                    })
-                    .collect();
-                let app_keybinds = AppKeybinds {
-                    application: state.current_application.clone(),
-                    keybinds: entries,
-                };
-                if let Ok(json) = serde_json::to_string_pretty(&app_keybinds) {
-                    if let Some(path) = rfd::FileDialog::new()
-                        .add_filter("json", &["json"])
-                        .set_file_name(&format!("{}.json", state.current_application))
-                        .save_file()
-                    {
-                        if fs::write(path, json).is_ok() {
-                            state.status_message = "Export successful.".to_string();
-                        } else {
-                            state.status_message = "Error: Failed to write to file.".to_string();
-                        }
-                    }
-                }
-                close_popup = true;
+            if state.message_history.is_empty() {
+                ui.label("No messages yet.");
+                return;
             }
 
-            if ui.button("Export All").clicked() {
-                if let Some(folder) = rfd::FileDialog::new().pick_folder() {
-                    for app_name in state.all_applications.iter() {
-                        let entries: Vec<_> = state
-                            .keybinds
-                            .iter()
-                            .filter(|kb| &kb.application == app_name)
-                            .map(|kb| KeybindEntry {
-                                keys: kb.keys.clone(),
-                                description: kb.description.clone(),
-                            })
-                            .collect();
-                        let app_keybinds = AppKeybinds {
-                            application: app_name.clone(),
-                            keybinds: entries,
-                        };
-                        if let Ok(json) = serde_json::to_string_pretty(&app_keybinds) {
-                            let path = folder.join(format!("{}.json", app_name));
-                            if fs::write(path, json).is_err() {
-                                state.status_message =
-                                    format!("Error writing file for {}.", app_name);
-                                break;
+            // Newest messages are listed first.
+            egui::ScrollArea::vertical()
+                .max_height(300.0)
+                .show(ui, |ui| {
+                    egui::Grid::new("messages_grid")
+                        .num_columns(2)
+                        .spacing([20.0, 4.0])
+                        .show(ui, |ui| {
+                            for (timestamp, severity, message) in state.message_history.iter().rev()
+                            {
+                                ui.label(RichText::new(format_elapsed(*timestamp)).monospace());
+                                ui.label(
+                                    RichText::new(message)
+                                        .monospace()
+                                        .color(severity.color(ui.visuals())),
+                                );
+                                ui.end_row();
                             }
-                        }
-                    }
-                    state.status_message = "Export all successful.".to_string();
-                }
+                        });
+                });
+        });
+    if close_popup {
+        state.mode = Mode::Normal;
+    }
+}
+
+/// The description of a modified keybind in `:diff`, with its use count,
+/// whether it's disabled and whether it's starred, so changes that only
+/// touch those show up.
+fn diff_text(entry: &KeybindEntry) -> String {
+    let mut text = entry.description.clone();
+    if entry.uses > 0 {
+        text.push_str(&format!(" (used {}×)", entry.uses));
+    }
+    if !entry.enabled {
+        text.push_str(" (disabled)");
+    }
+    if entry.starred {
+        text.push_str(" ★");
+    }
+    text
+}
+
+fn draw_diff_popup(ctx: &Context, state: &mut AppState) {
+    let mut close_popup = false;
+    egui::Window::new(format!("Changes to {}", state.current_application))
+        .anchor(egui::Align2::CENTER_CENTER, vec2(0.0, -100.0))
+        .collapsible(false)
+        .resizable(false)
+        .show(ctx, |ui| {
+            if ui.input(|i| i.key_pressed(Key::Escape) || i.key_pressed(Key::Enter)) {
                 close_popup = true;
             }
+
+            if state.diff_lines.is_empty() {
+                ui.label("No changes since the last save.");
+                return;
+            }
+
+            let added = Severity::Success.color(ui.visuals());
+            let removed = Severity::Error.color(ui.visuals());
+            let keys_text = |keys: &str| {
+                display_keys(
+                    keys,
+                    state.config.keybind_notation,
+                    state.config.key_display,
+                    &state.config.chord_delimiter,
+                )
+            };
+            egui::ScrollArea::vertical()
+                .max_height(300.0)
+                .show(ui, |ui| {
+                    egui::Grid::new("diff_grid")
+                        .num_columns(3)
+                        .spacing([20.0, 4.0])
+                        .show(ui, |ui| {
+                            for line in &state.diff_lines {
+                                match line {
+                                    DiffLine::Added(kb) => {
+                                        ui.label(RichText::new("+").monospace().color(added));
+                                        ui.label(
+                                            RichText::new(keys_text(&kb.keys))
+                                                .monospace()
+                                                .color(added),
+                                        );
+                                        ui.label(RichText::new(&kb.description).color(added));
+                                    }
+                                    DiffLine::Removed(kb) => {
+                                        ui.label(RichText::new("-").monospace().color(removed));
+                                        ui.label(
+                                            RichText::new(keys_text(&kb.keys))
+                                                .monospace()
+                                                .color(removed),
+                                        );
+                                        ui.label(RichText::new(&kb.description).color(removed));
+                                    }
+                                    DiffLine::Modified { old, new } => {
+                                        ui.label(RichText::new("~").monospace());
+                                        ui.label(RichText::new(keys_text(&new.keys)).monospace());
+                                        ui.vertical(|ui| {
+                                            ui.label(
+                                                RichText::new(format!("- {}", diff_text(old)))
+                                                    .color(removed),
+                                            );
+                                            ui.label(
+                                                RichText::new(format!("+ {}", diff_text(new)))
+                                                    .color(added),
+                                            );
+                                        });
+                                    }
+                                }
+                                ui.end_row();
+                            }
+                        });
+                });
         });
     if close_popup {
         state.mode = Mode::Normal;
     }
 }
 
-fn draw_import_popup(ctx: &Context, state: &mut AppState) {
+fn draw_load_failures_popup(ctx: &Context, state: &mut AppState) {
     let mut close_popup = false;
-    egui::Window::new("Import Keybinds")
+    let mut open_files = false;
+    egui::Window::new("Files that failed to load")
         .anchor(egui::Align2::CENTER_CENTER, vec2(0.0, -100.0))
         .collapsible(false)
         .resizable(false)
@@ -1335,68 +7231,274 @@ fn draw_import_popup(ctx: &Context, state: &mut AppState) {
             if ui.input(|i| i.key_pressed(Key::Escape)) {
                 close_popup = true;
             }
-            ui.label("Select a JSON file to import.");
-            ui.separator();
+            if ui.input(|i| i.key_pressed(Key::Enter)) {
+                open_files = true;
+                close_popup = true;
+            }
 
-            let import_logic = |replace: bool, state: &mut AppState| {
-                if let Some(path) = rfd::FileDialog::new()
-                    .add_filter("json", &["json"])
-                    .pick_file()
-                {
-                    if let Ok(data) = fs::read_to_string(path) {
-                        if let Ok(imported_app) = serde_json::from_str::<AppKeybinds>(&data) {
-                            state.push_to_undo_history();
-                            state
-                                .all_applications
-                                .insert(imported_app.application.clone());
-
-                            if replace {
-                                state
-                                    .keybinds
-                                    .retain(|kb| kb.application != imported_app.application);
-                            }
+            egui::ScrollArea::vertical()
+                .max_height(300.0)
+                .show(ui, |ui| {
+                    for (path, error) in &state.load_failures {
+                        ui.label(RichText::new(path.display().to_string()).monospace());
+                        ui.label(RichText::new(error).color(Severity::Error.color(ui.visuals())));
+                        ui.add_space(4.0);
+                    }
+                });
+            ui.separator();
+            ui.label("Enter opens the files to fix them; restart ctrlset afterwards to load them.");
+        });
+    if open_files {
+        let failed = state
+            .load_failures
+            .iter()
+            .filter(|(path, _)| open_with_system(path).is_err())
+            .count();
+        if failed == 0 {
+            state.set_status(Severity::Info, "Opened the files that failed to load.");
+        } else {
+            state.set_status(
+                Severity::Error,
+                format!("Couldn't open {} file(s).", failed),
+            );
+        }
+    }
+    if close_popup {
+        state.mode = Mode::Normal;
+    }
+}
 
-                            let existing_keybinds: HashSet<_> = state
-                                .keybinds
-                                .iter()
-                                .filter(|kb| kb.application == imported_app.application)
-                                .cloned()
-                                .collect();
-                            for entry in imported_app.keybinds {
-                                let new_kb = Keybind {
-                                    keys: entry.keys,
-                                    description: entry.description,
-                                    application: imported_app.application.clone(),
-                                };
-                                if !existing_keybinds.contains(&new_kb) {
-                                    state.keybinds.push(new_kb);
-                                }
-                            }
+fn draw_quit_confirm_popup(ctx: &Context, state: &mut AppState) {
+    let mut close_popup = false;
+    let mut save_all = false;
+    let mut quit = false;
+    egui::Window::new("Unsaved changes")
+        .anchor(egui::Align2::CENTER_CENTER, vec2(0.0, -100.0))
+        .collapsible(false)
+        .resizable(false)
+        .show(ctx, |ui| {
+            if ui.input(|i| i.key_pressed(Key::Escape)) {
+                close_popup = true;
+            }
+            if ui.input(|i| i.key_pressed(Key::S)) {
+                save_all = true;
+            }
+            if ui.input(|i| i.key_pressed(Key::Q)) {
+                quit = true;
+            }
 
-                            state.dirty = true;
-                            state.refilter();
-                            state.status_message = "Import successful.".to_string();
+            ui.label("These applications have unsaved changes:");
+            egui::ScrollArea::vertical()
+                .max_height(300.0)
+                .show(ui, |ui| {
+                    for app in &state.unsaved_on_quit {
+                        let label = if state.all_applications.contains(app) {
+                            app.clone()
                         } else {
-                            state.status_message = "Error: Failed to parse JSON file.".to_string();
-                        }
+                            format!("{} (removed)", app)
+                        };
+                        ui.label(RichText::new(label).color(state.app_color(app)));
                     }
+                });
+            ui.separator();
+            ui.horizontal(|ui| {
+                if ui.button("Save all and quit (s)").clicked() {
+                    save_all = true;
                 }
-            };
+                if ui.button("Quit anyway (q)").clicked() {
+                    quit = true;
+                }
+                if ui.button("Cancel (Esc)").clicked() {
+                    close_popup = true;
+                }
+            });
+        });
+    if save_all {
+        state.save_all_keybinds();
+        quit = !state.dirty;
+        close_popup = true;
+    }
+    if quit {
+        state.should_quit = true;
+    }
+    if close_popup {
+        state.mode = Mode::Normal;
+    }
+}
 
-            if ui.button("Import and Merge").clicked() {
-                import_logic(false, state);
+fn draw_force_quit_confirm_popup(ctx: &Context, state: &mut AppState) {
+    let mut close_popup = false;
+    let mut quit = false;
+    egui::Window::new("Discard changes?")
+        .anchor(egui::Align2::CENTER_CENTER, vec2(0.0, -100.0))
+        .collapsible(false)
+        .resizable(false)
+        .show(ctx, |ui| {
+            if ui.input(|i| i.key_pressed(Key::Escape) || i.key_pressed(Key::N)) {
                 close_popup = true;
             }
-            if ui.button("Import and Replace").clicked() {
-                import_logic(true, state);
-                close_popup = true;
+            if ui.input(|i| i.key_pressed(Key::Y)) {
+                quit = true;
             }
+
+            ui.label("Quit without saving? Unsaved changes will be lost.");
+            ui.separator();
+            ui.horizontal(|ui| {
+                if ui.button("Quit (y)").clicked() {
+                    quit = true;
+                }
+                if ui.button("Cancel (n)").clicked() {
+                    close_popup = true;
+                }
+            });
         });
+    if quit {
+        state.should_quit = true;
+    }
     if close_popup {
         state.mode = Mode::Normal;
     }
 }
 
+/// Formats a keymap key name the way it's typed, e.g. "K" as `k` and "Slash"
+/// as `/`.
+fn key_label(name: &str) -> String {
+    match name.to_uppercase().as_str() {
+        "SLASH" => "/".into(),
+        "COLON" => ":".into(),
+        "SEMICOLON" => ";".into(),
+        "SPACE" => "<Space>".into(),
+        "ESCAPE" => "<Esc>".into(),
+        "ENTER" => "<Enter>".into(),
+        "TAB" => "<Tab>".into(),
+        upper if upper.chars().count() == 1 => upper.to_lowercase(),
+        _ => format!("<{}>", name),
+    }
+}
+
+/// Like [`key_label`] but for keys that are bound with Shift held.
+fn shifted_key_label(name: &str) -> String {
+    if name.chars().count() == 1 {
+        name.to_uppercase()
+    } else {
+        format!("<Shift+{}>", name)
+    }
+}
+
+/// The Normal mode rows of the help popup, built from the loaded keymap.
+fn normal_mode_help(keymap: &Keymap) -> Vec<(String, &'static str)> {
+    let join = |names: &[String]| {
+        names
+            .iter()
+            .map(|name| key_label(name))
+            .collect::<Vec<_>>()
+            .join("/")
+    };
+    let leader = key_label(&keymap.leader);
+    let delete = key_label(&keymap.delete_leader);
+    let yank = key_label(&keymap.yank_leader);
+    vec![
+        (
+            format!("{}/{}", key_label(&keymap.down), key_label(&keymap.up)),
+            "Move down/up",
+        ),
+        (
+            format!("{}/{}", join(&keymap.left), join(&keymap.right)),
+            "Move left/right",
+        ),
+        (key_label(&keymap.goto_top).repeat(2), "Go to top"),
+        (shifted_key_label(&keymap.goto_bottom), "Go to bottom"),
+        ("Ctrl+D/Ctrl+U".into(), "Move half a page down/up"),
+        ("Ctrl+F/Ctrl+B".into(), "Move a full page down/up"),
+        (
+            "Ctrl+Tab/Ctrl+Shift+Tab".into(),
+            "Switch to the next/previous application",
+        ),
+        ("Ctrl+=/Ctrl+-/Ctrl+0".into(), "Zoom in/out/reset"),
+        (key_label(&keymap.insert_mode), "Enter Insert mode"),
+        (
+            key_label(&keymap.insert_text),
+            "Type the current row's keys as text",
+        ),
+        (
+            shifted_key_label(&keymap.insert_sequence),
+            "Record a key sequence for the current row",
+        ),
+        (key_label(&keymap.new_line_below), "Insert new row below"),
+        (
+            shifted_key_label(&keymap.new_line_above),
+            "Insert new row above",
+        ),
+        (
+            shifted_key_label(&keymap.append_line),
+            "Add a row at the end of the app",
+        ),
+        (
+            format!(
+                "{}/{}",
+                shifted_key_label(&keymap.move_down),
+                shifted_key_label(&keymap.move_up)
+            ),
+            "Move current row down/up",
+        ),
+        (key_label(&keymap.search_mode), "Enter Search mode"),
+        (key_label(&keymap.command_mode), "Enter Command mode"),
+        (key_label(&keymap.undo), "Undo last change"),
+        (
+            shifted_key_label(&keymap.quit_leader).repeat(2),
+            "Save and quit",
+        ),
+        (
+            format!("{}Q", shifted_key_label(&keymap.quit_leader)),
+            "Quit without saving",
+        ),
+        (
+            key_label(&keymap.mark_used),
+            "Mark selected keybind as used",
+        ),
+        (
+            format!("{}<label>", key_label(&keymap.jump)),
+            "Jump to a visible row by its label",
+        ),
+        (
+            format!("{0}<reg>...{0}", key_label(&keymap.record_macro)),
+            "Record a macro into a register",
+        ),
+        ("@<reg>".into(), "Replay the macro in a register"),
+        ("*".into(), "Star or unstar the selected keybind"),
+        (delete.repeat(2), "Delete current row"),
+        (
+            format!("{}{}", delete, key_label(&keymap.down)),
+            "Delete current and next row",
+        ),
+        (
+            format!("{}{}", delete, key_label(&keymap.up)),
+            "Delete current and previous row",
+        ),
+        (yank.repeat(2), "Copy current row to clipboard"),
+        (
+            format!("{}{}", leader, key_label(&keymap.app_filter)),
+            "Filter applications",
+        ),
+        (
+            format!("{}{}", leader, key_label(&keymap.export_menu)),
+            "Open export menu",
+        ),
+        (
+            format!("{}{}", leader, key_label(&keymap.import_menu)),
+            "Open import menu",
+        ),
+        (
+            format!("{}{}", leader, key_label(&keymap.command_palette)),
+            "Open command palette",
+        ),
+        (
+            format!("{}{}", leader, key_label(&keymap.toggle_enabled)),
+            "Disable/enable selected keybind",
+        ),
+    ]
+}
+
 fn draw_help_popup(ctx: &Context, state: &mut AppState) {
     let mut close_popup = false;
     egui::Window::new("Help")
@@ -1404,106 +7506,130 @@ fn draw_help_popup(ctx: &Context, state: &mut AppState) {
         .collapsible(false)
         .resizable(false)
         .show(ctx, |ui| {
-            if ui.input(|i| i.key_pressed(Key::Escape)) {
+            let search_id = egui::Id::new(HELP_SEARCH_ID);
+            let searching = ui.memory(|m| m.has_focus(search_id));
+            // Escape leaves the search box before closing the popup.
+            if ui.input(|i| i.key_pressed(Key::Escape)) && !searching {
                 close_popup = true;
             }
 
-            egui::ScrollArea::vertical().show(ui, |ui| {
-                ui.heading("Normal Mode");
-                egui::Grid::new("help_grid_normal")
-                    .num_columns(2)
-                    .spacing([40.0, 4.0])
-                    .show(ui, |ui| {
-                        ui.label(RichText::new("j/k").monospace());
-                        ui.label("Move up/down");
-                        ui.end_row();
-                        ui.label(RichText::new("h/l/b/w/e").monospace());
-                        ui.label("Move left/right");
-                        ui.end_row();
-                        ui.label(RichText::new("gg").monospace());
-                        ui.label("Go to top");
-                        ui.end_row();
-                        ui.label(RichText::new("G").monospace());
-                        ui.label("Go to bottom");
-                        ui.end_row();
-                        ui.label(RichText::new("i").monospace());
-                        ui.label("Enter Insert mode");
-                        ui.end_row();
-                        ui.label(RichText::new("o").monospace());
-                        ui.label("Insert new row below");
-                        ui.end_row();
-                        ui.label(RichText::new("O").monospace());
-                        ui.label("Insert new row above");
-                        ui.end_row();
-                        ui.label(RichText::new("/").monospace());
-                        ui.label("Enter Search mode");
-                        ui.end_row();
-                        ui.label(RichText::new(":").monospace());
-                        ui.label("Enter Command mode");
-                        ui.end_row();
-                        ui.label(RichText::new("u").monospace());
-                        ui.label("Undo last change");
-                        ui.end_row();
-                        ui.label(RichText::new("dd").monospace());
-                        ui.label("Delete current row");
-                        ui.end_row();
-                        ui.label(RichText::new("dj").monospace());
-                        ui.label("Delete current and next row");
-                        ui.end_row();
-                        ui.label(RichText::new("dk").monospace());
-                        ui.label("Delete current and previous row");
-                        ui.end_row();
-                        ui.label(RichText::new("<Space>f").monospace());
-                        ui.label("Filter applications");
-                        ui.end_row();
-                        ui.label(RichText::new("<Space>e").monospace());
-                        ui.label("Open export menu");
-                        ui.end_row();
-                        ui.label(RichText::new("<Space>i").monospace());
-                        ui.label("Open import menu");
-                        ui.end_row();
-                    });
+            // Outside the search box `/` starts a search, and j/k and
+            // PageUp/PageDown scroll.
+            let mut scroll_delta = 0.0;
+            if !searching {
+                let line = ui.text_style_height(&egui::TextStyle::Body) + 4.0;
+                let page = state.help_viewport_height;
+                let start_search = ui.input_mut(|i| {
+                    for (key, delta) in [
+                        (Key::J, line),
+                        (Key::K, -line),
+                        (Key::PageDown, page),
+                        (Key::PageUp, -page),
+                    ] {
+                        if i.consume_key(Modifiers::NONE, key) {
+                            scroll_delta += delta;
+                        }
+                    }
+                    let slash = i.consume_key(Modifiers::NONE, Key::Slash);
+                    if slash {
+                        i.events
+                            .retain(|e| !matches!(e, egui::Event::Text(text) if text == "/"));
+                    }
+                    slash
+                });
+                if start_search {
+                    ui.memory_mut(|m| m.request_focus(search_id));
+                }
+            }
 
-                ui.add_space(10.0);
-                ui.heading("Command Mode");
-                egui::Grid::new("help_grid_command")
-                    .num_columns(2)
-                    .spacing([40.0, 4.0])
-                    .show(ui, |ui| {
-                        ui.label(RichText::new(":w").monospace());
-                        ui.label("Save current application's keybinds");
-                        ui.end_row();
-                        ui.label(RichText::new(":wq").monospace());
-                        ui.label("Save and quit");
-                        ui.end_row();
-                        ui.label(RichText::new(":q").monospace());
-                        ui.label("Quit (fails if there are unsaved changes)");
-                        ui.end_row();
-                        ui.label(RichText::new(":q!").monospace());
-                        ui.label("Force quit without saving");
-                        ui.end_row();
-                        ui.label(RichText::new(":new <name>").monospace());
-                        ui.label("Create a new application group");
-                        ui.end_row();
-                        ui.label(RichText::new(":help").monospace());
-                        ui.label("Show this help menu");
-                        ui.end_row();
-                    });
+            let text_edit = ui.add(
+                egui::TextEdit::singleline(&mut state.help_query)
+                    .id(search_id)
+                    .hint_text("Press / to search actions..."),
+            );
+            if text_edit.changed() {
+                state.help_scroll_offset = 0.0;
+                scroll_delta = 0.0;
+            }
+            ui.separator();
 
-                ui.add_space(10.0);
-                ui.heading("Insert/Search/Command Modes");
-                egui::Grid::new("help_grid_other")
-                    .num_columns(2)
-                    .spacing([40.0, 4.0])
-                    .show(ui, |ui| {
-                        ui.label(RichText::new("Enter").monospace());
-                        ui.label("Confirm action");
-                        ui.end_row();
-                        ui.label(RichText::new("Escape").monospace());
-                        ui.label("Cancel action / return to Normal mode");
-                        ui.end_row();
-                    });
+            // Rows are filtered by what they do, not by their keys.
+            let matcher = SkimMatcherV2::default();
+            let query = state.help_query.trim();
+            let matches = |description: &str| {
+                query.is_empty() || matcher.fuzzy_match(description, query).is_some()
+            };
+            let normal_rows: Vec<_> = normal_mode_help(&state.config.keymap)
+                .into_iter()
+                .filter(|(_, description)| matches(description))
+                .collect();
+            let command_rows: Vec<_> = COMMANDS
+                .iter()
+                .filter(|(_, description)| matches(description))
+                .collect();
+            let other_rows: Vec<_> = [
+                ("Enter", "Confirm action"),
+                ("Escape", "Cancel action / return to Normal mode"),
+            ]
+            .into_iter()
+            .filter(|(_, description)| matches(description))
+            .collect();
+
+            let mut scroll_area = egui::ScrollArea::vertical();
+            if scroll_delta != 0.0 || text_edit.changed() {
+                scroll_area = scroll_area
+                    .vertical_scroll_offset((state.help_scroll_offset + scroll_delta).max(0.0));
+            }
+            let output = scroll_area.show(ui, |ui| {
+                if normal_rows.is_empty() && command_rows.is_empty() && other_rows.is_empty() {
+                    ui.label(RichText::new("No matching actions.").weak());
+                }
+                if !normal_rows.is_empty() {
+                    ui.heading("Normal Mode");
+                    egui::Grid::new("help_grid_normal")
+                        .num_columns(2)
+                        .spacing([40.0, 4.0])
+                        .show(ui, |ui| {
+                            for (keys, description) in &normal_rows {
+                                ui.label(RichText::new(keys).monospace());
+                                ui.label(*description);
+                                ui.end_row();
+                            }
+                        });
+                    ui.add_space(10.0);
+                }
+
+                if !command_rows.is_empty() {
+                    ui.heading("Command Mode");
+                    egui::Grid::new("help_grid_command")
+                        .num_columns(2)
+                        .spacing([40.0, 4.0])
+                        .show(ui, |ui| {
+                            for (name, description) in &command_rows {
+                                ui.label(RichText::new(format!(":{}", name)).monospace());
+                                ui.label(*description);
+                                ui.end_row();
+                            }
+                        });
+                    ui.add_space(10.0);
+                }
+
+                if !other_rows.is_empty() {
+                    ui.heading("Insert/Search/Command Modes");
+                    egui::Grid::new("help_grid_other")
+                        .num_columns(2)
+                        .spacing([40.0, 4.0])
+                        .show(ui, |ui| {
+                            for (keys, description) in &other_rows {
+                                ui.label(RichText::new(*keys).monospace());
+                                ui.label(*description);
+                                ui.end_row();
+                            }
+                        });
+                }
             });
+            state.help_scroll_offset = output.state.offset.y;
+            state.help_viewport_height = output.inner_rect.height();
 
             ui.separator();
             if ui.button("Close").clicked() {
@@ -1511,6 +7637,98 @@ fn draw_help_popup(ctx: &Context, state: &mut AppState) {
             }
         });
     if close_popup {
+        state.help_query.clear();
         state.mode = Mode::Normal;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn empty_state() -> AppState {
+        let mut state = AppState::with_config(Config::default(), false);
+        state.current_application = "test".to_string();
+        state.all_applications.insert("test".to_string());
+        state.refilter();
+        state
+    }
+
+    fn assert_editing_first_row(state: &AppState) {
+        assert_eq!(state.keybinds.len(), 1);
+        assert_eq!(state.filtered_items.len(), 1);
+        assert_eq!(state.selected_cell, (0, 0));
+        assert_eq!(state.mode, Mode::Insert);
+        assert!(state.is_listening_for_keybind);
+    }
+
+    #[test]
+    fn o_creates_first_row_in_empty_list() {
+        let mut state = empty_state();
+        state.insert_new_keybind(false);
+        assert_editing_first_row(&state);
+    }
+
+    #[test]
+    fn shift_o_creates_first_row_in_empty_list() {
+        let mut state = empty_state();
+        state.insert_new_keybind(true);
+        assert_editing_first_row(&state);
+    }
+
+    #[test]
+    fn new_row_is_editable_when_search_hides_everything() {
+        let mut state = empty_state();
+        state.search_query = "app:nothing".to_string();
+        state.refilter();
+        state.insert_new_keybind(true);
+        assert_editing_first_row(&state);
+    }
+
+    #[test]
+    fn validate_keybind_accepts_real_chords() {
+        for keys in [
+            "Ctrl+A",
+            "Ctrl+Shift+T",
+            "Ctrl+K Ctrl+C",
+            "Ctrl++",
+            "+",
+            "g g",
+            "Prefix c",
+            "C-x C-s",
+            "<C-w>",
+            "ctrl+k ctrl+s",
+        ] {
+            assert_eq!(validate_keybind(keys, " "), Ok(()), "{}", keys);
+        }
+    }
+
+    #[test]
+    fn validate_keybind_flags_two_keys_in_one_chord() {
+        assert!(validate_keybind("A+B", " ").is_err());
+        assert!(validate_keybind("Ctrl+A+B", " ").is_err());
+        assert!(validate_keybind("Ctrl+K Ctrl+A+B", " ").is_err());
+        assert!(validate_keybind("A++", " ").is_err());
+    }
+
+    #[test]
+    fn validate_keybind_flags_repeated_modifiers() {
+        assert!(validate_keybind("Ctrl+Ctrl+A", " ").is_err());
+        assert!(validate_keybind("Ctrl+Control+A", " ").is_err());
+        assert!(validate_keybind("shift+Alt+Shift+X", " ").is_err());
+    }
+
+    #[test]
+    fn validate_keybind_splits_on_chord_delimiter() {
+        assert_eq!(validate_keybind("Ctrl+K,Ctrl+C", ","), Ok(()));
+        assert_eq!(validate_keybind("Ctrl+K, Ctrl+C", ", "), Ok(()));
+        assert_eq!(validate_keybind("Ctrl+K → Ctrl+C", " → "), Ok(()));
+        assert!(validate_keybind("Ctrl+K,Ctrl+A+B", ",").is_err());
+    }
+
+    #[test]
+    fn validate_keybind_flags_missing_key() {
+        assert!(validate_keybind("Ctrl+", " ").is_err());
+        assert!(validate_keybind("Ctrl+Shift+", " ").is_err());
+    }
+}